@@ -226,13 +226,14 @@ export function GifAnalyzerPanel({ file }: GifAnalyzerPanelProps) {
 								['Dimensions', `${analysis.width}×${analysis.height}px`],
 								['File Size', formatFileSize(analysis.fileSize)],
 								['Frames', String(analysis.frames)],
+								// NETSCAPE stores repeats after the first play, so N means N + 1 plays.
 								[
 									'Loop Count',
 									analysis.loopCount === null
 										? 'Not set (play once)'
 										: analysis.loopCount === 0
 											? 'Infinite'
-											: `${analysis.loopCount}×`,
+											: `${analysis.loopCount + 1}×`,
 								],
 							]}
 						/>
@@ -12,14 +12,32 @@ export interface GifEncodeOptions {
 	speed?: number;
 	loopCount?: number;
 	frameDelaysCs?: number[];
+	deterministic?: boolean;
+	globalPalette?: boolean;
+	deltaFrames?: boolean;
+	dither?: boolean;
 	onProgress?: (progress: number) => void;
 }
 
 /**
  * Encode RGBA frames into a GIF using the Rust WASM encoder.
+ * Rejects with an Error naming the failing frame if encoding fails.
  */
 export async function encodeGif(options: GifEncodeOptions): Promise<Blob> {
-	const { frames, width, height, fps, maxColors = 256, speed = 10, loopCount = 0, frameDelaysCs } = options;
+	const {
+		frames,
+		width,
+		height,
+		fps,
+		maxColors = 256,
+		speed = 10,
+		loopCount = 0,
+		frameDelaysCs,
+		deterministic = false,
+		globalPalette = false,
+		deltaFrames = false,
+		dither = false,
+	} = options;
 
 	const wasm =
 		(await import('../../../../wasm/vixely_core.js')) as typeof import('../../../../wasm/vixely_core.js') & {
@@ -31,6 +49,7 @@ export async function encodeGif(options: GifEncodeOptions): Promise<Blob> {
 				delay_cs: number,
 				max_colors: number,
 				speed: number,
+				loop_count: number,
 			) => Uint8Array;
 			encode_gif_frames_ex: (
 				rgba_data: Uint8Array,
@@ -42,6 +61,11 @@ export async function encodeGif(options: GifEncodeOptions): Promise<Blob> {
 				speed: number,
 				loop_count: number,
 				frame_delays_cs: Uint16Array,
+				deterministic: boolean,
+				global_palette: boolean,
+				delta_frames: boolean,
+				dither: boolean,
+				on_progress?: (frameIndex: number, frameCount: number) => void,
 			) => Uint8Array;
 		};
 
@@ -57,39 +81,53 @@ export async function encodeGif(options: GifEncodeOptions): Promise<Blob> {
 
 	const delayCentiseconds = Math.round(100 / fps);
 
-	options.onProgress?.(0.5);
+	const { onProgress } = options;
+	const reportFrame = onProgress
+		? (frameIndex: number, frameCount: number) => onProgress((frameIndex + 1) / frameCount)
+		: undefined;
 
 	const hasPerFrameDelays = frameDelaysCs && frameDelaysCs.length > 0;
-	const needsExtended = loopCount !== 0 || hasPerFrameDelays;
+	const needsExtended = hasPerFrameDelays || deterministic || globalPalette || deltaFrames || dither || reportFrame;
 
 	let gifBytes: Uint8Array;
 
-	if (needsExtended) {
-		const delays = hasPerFrameDelays ? new Uint16Array(frameDelaysCs) : new Uint16Array(0);
-		gifBytes = wasm.encode_gif_frames_ex(
-			concatenated,
-			width,
-			height,
-			frames.length,
-			delayCentiseconds,
-			maxColors,
-			speed,
-			loopCount,
-			delays,
-		);
-	} else {
-		gifBytes = wasm.encode_gif_frames(
-			concatenated,
-			width,
-			height,
-			frames.length,
-			delayCentiseconds,
-			maxColors,
-			speed,
-		);
+	// The encoders throw a plain string on failure; rethrow as an Error so callers can show it.
+	try {
+		if (needsExtended) {
+			const delays = hasPerFrameDelays ? new Uint16Array(frameDelaysCs) : new Uint16Array(0);
+			gifBytes = wasm.encode_gif_frames_ex(
+				concatenated,
+				width,
+				height,
+				frames.length,
+				delayCentiseconds,
+				maxColors,
+				speed,
+				loopCount,
+				delays,
+				deterministic,
+				globalPalette,
+				deltaFrames,
+				dither,
+				reportFrame,
+			);
+		} else {
+			gifBytes = wasm.encode_gif_frames(
+				concatenated,
+				width,
+				height,
+				frames.length,
+				delayCentiseconds,
+				maxColors,
+				speed,
+				loopCount,
+			);
+		}
+	} catch (err) {
+		throw err instanceof Error ? err : new Error(String(err));
 	}
 
-	options.onProgress?.(1);
+	onProgress?.(1);
 
 	const stableBytes = new Uint8Array(gifBytes.byteLength);
 	stableBytes.set(gifBytes);
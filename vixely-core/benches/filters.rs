@@ -0,0 +1,45 @@
+//! Times the pixel-loop filters on a 4K frame.
+//!
+//! Compare the serial and rayon paths by running both:
+//! `cargo bench --bench filters` and `cargo bench --bench filters --features parallel`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use vixely_core::{apply_filters, apply_gaussian_blur, decontaminate_edges};
+
+const WIDTH: u32 = 3840;
+const HEIGHT: u32 = 2160;
+const ITERATIONS: u32 = 10;
+
+fn bench(name: &str, mut run: impl FnMut(&mut [u8])) {
+    let mut image: Vec<u8> = (0..WIDTH * HEIGHT * 4)
+        .map(|i| (i * 31 % 251) as u8)
+        .collect();
+
+    run(&mut image);
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        run(black_box(&mut image));
+    }
+    let per_iter = start.elapsed() / ITERATIONS;
+
+    println!("{name:<24} {per_iter:>10.2?} / {WIDTH}x{HEIGHT} frame");
+}
+
+fn main() {
+    let mode = if cfg!(feature = "parallel") {
+        "parallel"
+    } else {
+        "serial"
+    };
+    println!("vixely-core filters ({mode})");
+
+    bench("apply_filters", |img| apply_filters(img, 0.05, 1.1, 1.2));
+    bench("apply_gaussian_blur", |img| {
+        apply_gaussian_blur(img, WIDTH, HEIGHT, 4.0)
+    });
+    bench("decontaminate_edges", |img| {
+        decontaminate_edges(img, WIDTH, HEIGHT)
+    });
+}
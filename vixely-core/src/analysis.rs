@@ -0,0 +1,112 @@
+//! Read-only measurements over pixel buffers.
+
+use wasm_bindgen::prelude::*;
+
+use crate::filters::luma_u8;
+
+/// Per-channel difference statistics between two RGBA images.
+#[wasm_bindgen]
+pub struct DiffResult {
+    mean_abs_error: f64,
+    max_diff: u8,
+    differing_pixels: u32,
+}
+
+#[wasm_bindgen]
+impl DiffResult {
+    /// Mean absolute difference over every RGBA channel, in 0..255.
+    #[wasm_bindgen(getter)]
+    pub fn mean_abs_error(&self) -> f64 {
+        self.mean_abs_error
+    }
+
+    /// Largest difference seen in any single channel.
+    #[wasm_bindgen(getter)]
+    pub fn max_diff(&self) -> u8 {
+        self.max_diff
+    }
+
+    /// Pixels where at least one channel differs by more than the threshold.
+    #[wasm_bindgen(getter)]
+    pub fn differing_pixels(&self) -> u32 {
+        self.differing_pixels
+    }
+}
+
+/// Compare two RGBA images channel by channel.
+///
+/// `a`, `b`: RGBA pixels, both `width * height * 4` bytes
+/// `threshold`: per-channel difference a pixel must exceed to count as differing
+///
+/// Alpha is compared like the color channels. Returns `None` if either buffer
+/// doesn't match the dimensions.
+#[wasm_bindgen]
+pub fn image_diff(
+    a: &[u8],
+    b: &[u8],
+    width: u32,
+    height: u32,
+    threshold: u8,
+) -> Option<DiffResult> {
+    let len = width as usize * height as usize * 4;
+    if a.len() != len || b.len() != len {
+        return None;
+    }
+
+    let mut total = 0u64;
+    let mut max_diff = 0u8;
+    let mut differing_pixels = 0u32;
+    for (pa, pb) in a.chunks_exact(4).zip(b.chunks_exact(4)) {
+        let mut pixel_max = 0u8;
+        for (&ca, &cb) in pa.iter().zip(pb) {
+            let d = ca.abs_diff(cb);
+            total += d as u64;
+            pixel_max = pixel_max.max(d);
+        }
+        max_diff = max_diff.max(pixel_max);
+        if pixel_max > threshold {
+            differing_pixels += 1;
+        }
+    }
+
+    Some(DiffResult {
+        mean_abs_error: if len == 0 {
+            0.0
+        } else {
+            total as f64 / len as f64
+        },
+        max_diff,
+        differing_pixels,
+    })
+}
+
+/// Per-channel histograms in R, G, B, luma order.
+pub(crate) fn histograms(image_data: &[u8], ignore_transparent: bool) -> [[u32; 256]; 4] {
+    let mut hist = [[0u32; 256]; 4];
+    for px in image_data.chunks_exact(4) {
+        if ignore_transparent && px[3] == 0 {
+            continue;
+        }
+        hist[0][px[0] as usize] += 1;
+        hist[1][px[1] as usize] += 1;
+        hist[2][px[2] as usize] += 1;
+        hist[3][luma_u8(px[0], px[1], px[2]) as usize] += 1;
+    }
+    hist
+}
+
+/// Count pixel values for a levels/curves histogram.
+///
+/// `image_data`: RGBA pixels
+/// `ignore_transparent`: skip pixels whose alpha is 0
+///
+/// Returns 1024 counts: 256 bins each for red, green, blue and BT.709 luma, in
+/// that order, so bin `v` of channel `c` is at index `c * 256 + v`. Buffers
+/// whose length isn't a multiple of 4 yield all-zero counts.
+#[wasm_bindgen]
+pub fn compute_histogram(image_data: &[u8], ignore_transparent: bool) -> Vec<u32> {
+    if !image_data.len().is_multiple_of(4) {
+        return vec![0; 256 * 4];
+    }
+    histograms(image_data, ignore_transparent).concat()
+}
@@ -0,0 +1,61 @@
+//! Animated PNG encoding.
+
+use png::{BitDepth, ColorType, Encoder};
+use wasm_bindgen::prelude::*;
+
+use crate::frames::{checked_frames, encode_error};
+
+/// Encode RGBA frames into a lossless animated PNG with full alpha.
+///
+/// `rgba_data`: all frames concatenated (width * height * 4 bytes per frame)
+/// `width`, `height`: frame dimensions
+/// `frame_count`: number of frames, at least 1
+/// `delay_num`, `delay_den`: frame delay as a fraction of a second, e.g. 1/30
+/// (a denominator of 0 means 1/100 s, per the APNG spec)
+/// `loop_count`: 0 = loop forever, N = play N times in total
+///
+/// Takes the same concatenated input as [`crate::encode_gif_frames`]. Returns an
+/// error if a dimension is 0, `rgba_data` holds fewer than `frame_count` frames,
+/// or encoding fails.
+#[wasm_bindgen]
+pub fn encode_apng_frames(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    frame_count: u32,
+    delay_num: u16,
+    delay_den: u16,
+    loop_count: u32,
+) -> Result<Vec<u8>, JsValue> {
+    if width == 0 || height == 0 || frame_count == 0 {
+        return Err(JsValue::from_str(
+            "APNG needs non-zero dimensions and at least one frame",
+        ));
+    }
+    let (frames, frame_size) = checked_frames(rgba_data, width, height, frame_count)?;
+
+    let mut output = Vec::new();
+    {
+        let mut encoder = Encoder::new(&mut output, width, height);
+        encoder.set_color(ColorType::Rgba);
+        encoder.set_depth(BitDepth::Eight);
+        encoder
+            .set_animated(frame_count, loop_count)
+            .and_then(|_| encoder.set_frame_delay(delay_num, delay_den))
+            .map_err(|e| encode_error("failed to write APNG header", e))?;
+
+        let mut writer = encoder
+            .write_header()
+            .map_err(|e| encode_error("failed to write APNG header", e))?;
+        for (i, frame) in frames.chunks_exact(frame_size).enumerate() {
+            writer
+                .write_image_data(frame)
+                .map_err(|e| encode_error(&format!("failed to write frame {i}"), e))?;
+        }
+        writer
+            .finish()
+            .map_err(|e| encode_error("failed to finish APNG", e))?;
+    }
+
+    Ok(output)
+}
@@ -0,0 +1,58 @@
+/// Pack 8-bit RGB channels into a `0xRRGGBB` value.
+#[inline]
+pub fn pack_rgb(r: u8, g: u8, b: u8) -> u32 {
+    (r as u32) << 16 | (g as u32) << 8 | b as u32
+}
+
+/// Split a `0xRRGGBB` value into its 8-bit channels. The top byte is ignored.
+#[inline]
+pub fn unpack_rgb(color: u32) -> (u8, u8, u8) {
+    ((color >> 16) as u8, (color >> 8) as u8, color as u8)
+}
+
+/// Decode an sRGB-encoded channel in 0..1 to linear light.
+#[inline]
+pub(crate) fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Encode a linear-light channel in 0..1 back to sRGB.
+#[inline]
+pub(crate) fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Linear-light value of every 8-bit sRGB code, indexed by the code.
+pub(crate) fn srgb_decode_lut() -> [f32; 256] {
+    std::array::from_fn(|i| srgb_to_linear(i as f32 / 255.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packed_rgb_round_trips_in_channel_order() {
+        assert_eq!(pack_rgb(0x12, 0x34, 0x56), 0x12_3456);
+        assert_eq!(unpack_rgb(0x12_3456), (0x12, 0x34, 0x56));
+        assert_eq!(unpack_rgb(0xFF12_3456), (0x12, 0x34, 0x56));
+
+        for (r, g, b) in [
+            (0, 0, 0),
+            (255, 0, 0),
+            (0, 255, 0),
+            (0, 0, 255),
+            (255, 255, 255),
+        ] {
+            assert_eq!(unpack_rgb(pack_rgb(r, g, b)), (r, g, b));
+        }
+    }
+}
@@ -0,0 +1,317 @@
+//! Neighborhood operations that sample surrounding pixels.
+
+use wasm_bindgen::prelude::*;
+
+use crate::filters::luma;
+use crate::parallel::for_each_row;
+use crate::resize::premultiply;
+
+/// Border handling for [`pad_image`].
+const PAD_CLAMP: u32 = 0;
+const PAD_REFLECT: u32 = 1;
+const PAD_WRAP: u32 = 2;
+const PAD_ZERO: u32 = 3;
+
+/// Map a possibly out-of-range coordinate onto `0..n`, or `None` for zero padding.
+#[inline]
+fn pad_coord(i: isize, n: isize, mode: u32) -> Option<usize> {
+    let i = match mode {
+        PAD_REFLECT if n > 1 => {
+            let period = 2 * (n - 1);
+            let m = i.rem_euclid(period);
+            if m >= n { period - m } else { m }
+        }
+        PAD_WRAP => i.rem_euclid(n),
+        PAD_ZERO if i < 0 || i >= n => return None,
+        _ => i.clamp(0, n - 1),
+    };
+    Some(i as usize)
+}
+
+/// Copy an RGBA image into a larger buffer with a `pad`-pixel border.
+///
+/// `src`: RGBA pixels, `w * h * 4` bytes
+/// `pad`: border width added on every side
+/// `mode`: 0 = clamp (repeat edge pixels), 1 = reflect (mirror without
+/// repeating the edge), 2 = wrap (tile), 3 = zero (transparent black).
+/// Unknown modes clamp.
+///
+/// Returns the padded pixels with their width and height, letting neighborhood
+/// filters sample up to `pad` pixels out without bounds checks. An empty buffer
+/// with zero dimensions is returned when `src` doesn't match `w * h * 4`.
+pub fn pad_image(src: &[u8], w: u32, h: u32, pad: u32, mode: u32) -> (Vec<u8>, u32, u32) {
+    let (sw, sh) = (w as usize, h as usize);
+    if sw == 0 || sh == 0 || src.len() != sw * sh * 4 {
+        return (Vec::new(), 0, 0);
+    }
+
+    let p = pad as usize;
+    let (dw, dh) = (sw + 2 * p, sh + 2 * p);
+    let mut out = vec![0u8; dw * dh * 4];
+
+    for (dy, row) in out.chunks_exact_mut(dw * 4).enumerate() {
+        let Some(sy) = pad_coord(dy as isize - p as isize, sh as isize, mode) else {
+            continue;
+        };
+        let src_row = &src[sy * sw * 4..(sy + 1) * sw * 4];
+        row[p * 4..(p + sw) * 4].copy_from_slice(src_row);
+        for dx in (0..p).chain(p + sw..dw) {
+            if let Some(sx) = pad_coord(dx as isize - p as isize, sw as isize, mode) {
+                row[dx * 4..dx * 4 + 4].copy_from_slice(&src_row[sx * 4..sx * 4 + 4]);
+            }
+        }
+    }
+
+    (out, dw as u32, dh as u32)
+}
+
+/// Sobel gradient magnitude of the luma channel, one value per pixel.
+///
+/// Luma is normalized to 0..1 and border pixels clamp-sample the edge, so a
+/// hard black/white step yields a magnitude of 4.
+pub(crate) fn sobel_magnitude(image_data: &[u8], width: usize, height: usize) -> Vec<f32> {
+    let (padded, pw, _) = pad_image(image_data, width as u32, height as u32, 1, PAD_CLAMP);
+    let pw = pw as usize;
+    let lum: Vec<f32> = padded
+        .chunks_exact(4)
+        .map(|px| luma(px[0], px[1], px[2]) / 255.0)
+        .collect();
+
+    let mut out = Vec::with_capacity(width * height);
+    for y in 1..=height {
+        let (up, mid, down) = (&lum[(y - 1) * pw..], &lum[y * pw..], &lum[(y + 1) * pw..]);
+        for x in 1..=width {
+            let gx = up[x + 1] + 2.0 * mid[x + 1] + down[x + 1]
+                - up[x - 1]
+                - 2.0 * mid[x - 1]
+                - down[x - 1];
+            let gy =
+                down[x - 1] + 2.0 * down[x] + down[x + 1] - up[x - 1] - 2.0 * up[x] - up[x + 1];
+            out.push((gx * gx + gy * gy).sqrt());
+        }
+    }
+    out
+}
+
+/// Normalized 1D Gaussian weights spanning `-radius..=radius`, with
+/// `radius = ceil(3 * sigma)` capped at `max_radius`.
+fn gaussian_kernel(sigma: f32, max_radius: usize) -> Vec<f32> {
+    let radius = ((3.0 * sigma).ceil() as usize).min(max_radius) as isize;
+    let weights: Vec<f32> = (-radius..=radius)
+        .map(|i| (-(i * i) as f32 / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let sum: f32 = weights.iter().sum();
+    weights.into_iter().map(|w| w / sum).collect()
+}
+
+/// Separable Gaussian blur of every RGBA channel, edges clamp-sampled.
+///
+/// Color is premultiplied by alpha while sampling so transparent pixels don't
+/// bleed into the edges; where the result is fully transparent the source color
+/// is kept. The kernel radius is capped at the image's larger side, past which
+/// clamped samples only repeat the border.
+///
+/// Returns unrounded straight-alpha `f32` channels (0..255) so callers can keep
+/// the precision. `sigma` must be positive.
+pub(crate) fn gaussian_blur(src: &[u8], width: usize, height: usize, sigma: f32) -> Vec<f32> {
+    let kernel = gaussian_kernel(sigma, width.max(height));
+    let r = kernel.len() / 2;
+    let (padded, pw, ph) = pad_image(src, width as u32, height as u32, r as u32, PAD_CLAMP);
+    let padded = premultiply(&padded);
+    let (pw, ph) = (pw as usize, ph as usize);
+
+    // Horizontal pass over every padded row, keeping only the `width` inner columns.
+    let mut horizontal = vec![0.0f32; width * ph * 4];
+    for_each_row(&mut horizontal, width, |y, out| {
+        let row = &padded[y * pw * 4..(y + 1) * pw * 4];
+        for x in 0..width {
+            let mut acc = [0.0f32; 4];
+            for (k, &w) in kernel.iter().enumerate() {
+                let s = (x + k) * 4;
+                for c in 0..4 {
+                    acc[c] += row[s + c] * w;
+                }
+            }
+            out[x * 4..x * 4 + 4].copy_from_slice(&acc);
+        }
+    });
+
+    // Vertical pass back down to `height` rows, then back to straight alpha.
+    let mut out = vec![0.0f32; width * height * 4];
+    for_each_row(&mut out, width, |y, dst_row| {
+        for (k, &w) in kernel.iter().enumerate() {
+            let src_row = &horizontal[(y + k) * width * 4..(y + k + 1) * width * 4];
+            for (d, &s) in dst_row.iter_mut().zip(src_row) {
+                *d += s * w;
+            }
+        }
+
+        let orig_row = &src[y * width * 4..(y + 1) * width * 4];
+        for (px, orig) in dst_row.chunks_exact_mut(4).zip(orig_row.chunks_exact(4)) {
+            if px[3] > 0.0 {
+                let scale = 255.0 / px[3];
+                px[..3].iter_mut().for_each(|v| *v *= scale);
+            } else {
+                for (v, &o) in px[..3].iter_mut().zip(orig) {
+                    *v = o as f32;
+                }
+            }
+        }
+    });
+    out
+}
+
+/// Sharpen with an unsharp mask.
+///
+/// `image_data`: RGBA pixels, modified in place
+/// `width`, `height`: image dimensions
+/// `radius`: Gaussian sigma of the blurred copy, in pixels
+/// `amount`: how much of `original - blurred` is added back (1 = 100%)
+/// `threshold`: channels whose difference from the blur is at or below this are
+/// left alone, so flat areas and noise aren't sharpened
+///
+/// Edge pixels clamp-sample the border. Returns early unless
+/// `width * height * 4 == image_data.len()`. Alpha is untouched.
+#[wasm_bindgen]
+pub fn apply_unsharp_mask(
+    image_data: &mut [u8],
+    width: u32,
+    height: u32,
+    radius: f32,
+    amount: f32,
+    threshold: u8,
+) {
+    let (w, h) = (width as usize, height as usize);
+    if image_data.len() != w * h * 4 || radius <= 0.0 || amount == 0.0 {
+        return;
+    }
+
+    let blurred = gaussian_blur(image_data, w, h, radius);
+    for_each_row(image_data, w, |y, row| {
+        let blurred_row = &blurred[y * w * 4..(y + 1) * w * 4];
+        for (px, bl) in row.chunks_exact_mut(4).zip(blurred_row.chunks_exact(4)) {
+            for c in 0..3 {
+                let diff = px[c] as f32 - bl[c];
+                if diff.abs() > threshold as f32 {
+                    px[c] = (px[c] as f32 + amount * diff).round().clamp(0.0, 255.0) as u8;
+                }
+            }
+        }
+    });
+}
+
+/// Blur with a separable Gaussian.
+///
+/// `image_data`: RGBA pixels, modified in place
+/// `width`, `height`: image dimensions
+/// `sigma`: standard deviation in pixels; the kernel spans `ceil(3 * sigma)`
+/// pixels each side, up to the image's larger side. Non-positive values are a
+/// no-op.
+///
+/// All four channels are blurred so soft alpha edges stay consistent with the
+/// color, and color is weighted by alpha so transparent pixels don't darken
+/// them. Edge pixels clamp-sample the border. Returns early unless
+/// `width * height * 4 == image_data.len()`.
+#[wasm_bindgen]
+pub fn apply_gaussian_blur(image_data: &mut [u8], width: u32, height: u32, sigma: f32) {
+    let (w, h) = (width as usize, height as usize);
+    if image_data.len() != w * h * 4 || sigma <= 0.0 {
+        return;
+    }
+
+    let blurred = gaussian_blur(image_data, w, h, sigma);
+    for (dst, &src) in image_data.iter_mut().zip(&blurred) {
+        *dst = src.round().clamp(0.0, 255.0) as u8;
+    }
+}
+
+/// Convolve RGB with an arbitrary square kernel.
+///
+/// `image_data`: RGBA pixels, modified in place
+/// `width`, `height`: image dimensions
+/// `kernel`: `kernel_size * kernel_size` weights in row-major order
+/// `kernel_size`: side length, must be odd
+/// `divisor`: the weighted sum is divided by this; 0 is treated as 1
+/// `bias`: added after dividing, in 0..255 units (e.g. 128 for emboss)
+///
+/// Edge pixels clamp-sample the border and results are clamped. Returns early
+/// unless `width * height * 4 == image_data.len()` and the kernel is well
+/// formed. Alpha is untouched.
+#[allow(clippy::too_many_arguments)] // wasm-bindgen exports a flat ABI, so options can't be grouped into a struct
+#[wasm_bindgen]
+pub fn apply_convolution(
+    image_data: &mut [u8],
+    width: u32,
+    height: u32,
+    kernel: &[f32],
+    kernel_size: u32,
+    divisor: f32,
+    bias: f32,
+) {
+    let (w, h, n) = (width as usize, height as usize, kernel_size as usize);
+    if image_data.len() != w * h * 4 || n.is_multiple_of(2) || kernel.len() != n * n {
+        return;
+    }
+
+    let scale = if divisor == 0.0 { 1.0 } else { 1.0 / divisor };
+    let (padded, pw, _) = pad_image(image_data, width, height, kernel_size / 2, PAD_CLAMP);
+    let stride = pw as usize * 4;
+
+    for_each_row(image_data, w, |y, row| {
+        for (x, px) in row.chunks_exact_mut(4).enumerate() {
+            let mut acc = [0.0f32; 3];
+            for (ky, weights) in kernel.chunks_exact(n).enumerate() {
+                let src = &padded[(y + ky) * stride + x * 4..];
+                for (kx, &k) in weights.iter().enumerate() {
+                    let s = &src[kx * 4..kx * 4 + 3];
+                    for (a, &v) in acc.iter_mut().zip(s) {
+                        *a += v as f32 * k;
+                    }
+                }
+            }
+            for (v, a) in px[..3].iter_mut().zip(acc) {
+                *v = (a * scale + bias).round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gaussian_kernel_caps_huge_sigma() {
+        let kernel = gaussian_kernel(1e9, 8);
+        assert_eq!(kernel.len(), 17);
+        assert!((kernel.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn huge_sigma_blurs_within_source_range() {
+        let mut image: Vec<u8> = (0..8 * 8)
+            .flat_map(|i| [(i * 4) as u8, 50, 200, 255])
+            .collect();
+        apply_gaussian_blur(&mut image, 8, 8, 1e9);
+        // The weights are flat, so each pixel lands near the clamped window's
+        // mean instead of underflowing to nothing.
+        for px in image.chunks_exact(4) {
+            assert!((70..=182).contains(&px[0]), "{px:?}");
+            assert_eq!(&px[1..], &[50, 200, 255]);
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_blur_matches_single_thread() {
+        let pool = |threads| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+        };
+        let image: Vec<u8> = (0..64 * 48 * 4u32).map(|i| (i * 31 % 251) as u8).collect();
+        let blur = || gaussian_blur(&image, 64, 48, 2.5);
+        assert_eq!(pool(1).install(blur), pool(4).install(blur));
+    }
+}
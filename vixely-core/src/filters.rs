@@ -0,0 +1,1371 @@
+use wasm_bindgen::prelude::*;
+
+use crate::analysis::histograms;
+use crate::color::{linear_to_srgb, srgb_decode_lut, unpack_rgb};
+use crate::convolve::sobel_magnitude;
+use crate::parallel::{for_each_pixel, for_each_pixel_indexed, for_each_row};
+
+/// BT.709 luma coefficients, matching the WebGL color-correction shader.
+const LUMA_R: f32 = 0.2126;
+const LUMA_G: f32 = 0.7152;
+const LUMA_B: f32 = 0.0722;
+
+#[inline]
+pub(crate) fn luma(r: u8, g: u8, b: u8) -> f32 {
+    LUMA_R * r as f32 + LUMA_G * g as f32 + LUMA_B * b as f32
+}
+
+pub(crate) fn luma_u8(r: u8, g: u8, b: u8) -> u8 {
+    luma(r, g, b).round() as u8
+}
+
+#[inline]
+fn to_u8(v: f32) -> u8 {
+    (v * 255.0).round().clamp(0.0, 255.0) as u8
+}
+
+/// Brightness, contrast and saturation on normalized RGB, in the same order as
+/// the WebGL color-correction shader.
+#[inline]
+fn adjust_rgb(rgb: [f32; 3], brightness: f32, contrast: f32, saturation: f32) -> [f32; 3] {
+    let [r, g, b] = rgb.map(|c| (c + brightness - 0.5) * contrast + 0.5);
+    let gray = LUMA_R * r + LUMA_G * g + LUMA_B * b;
+    [r, g, b].map(|c| gray + (c - gray) * saturation)
+}
+
+/// Apply brightness, contrast and saturation to RGBA pixels.
+///
+/// `image_data`: RGBA pixels, modified in place
+/// `brightness`: additive offset on normalized channels (0 = unchanged)
+/// `contrast`: multiplier around mid-gray 0.5 (1 = unchanged)
+/// `saturation`: blend from BT.709 gray (0) through the original (1) and beyond
+///
+/// Alpha is untouched. Buffers whose length isn't a multiple of 4 are ignored.
+#[wasm_bindgen]
+pub fn apply_filters(image_data: &mut [u8], brightness: f32, contrast: f32, saturation: f32) {
+    if !image_data.len().is_multiple_of(4) {
+        return;
+    }
+
+    for_each_pixel(image_data, |px| {
+        let rgb = [px[0], px[1], px[2]].map(|c| c as f32 / 255.0);
+        let out = adjust_rgb(rgb, brightness, contrast, saturation);
+        px[0] = to_u8(out[0]);
+        px[1] = to_u8(out[1]);
+        px[2] = to_u8(out[2]);
+    });
+}
+
+/// [`apply_filters`] computed in linear light instead of sRGB-encoded values.
+///
+/// Channels are decoded from sRGB before brightness, contrast and saturation
+/// and re-encoded afterwards, which keeps hues truer when saturating. The BT.709
+/// luma used for saturation is then taken over linear values, as the standard
+/// intends. [`apply_filters`] keeps its sRGB behavior.
+#[wasm_bindgen]
+pub fn apply_filters_linear(
+    image_data: &mut [u8],
+    brightness: f32,
+    contrast: f32,
+    saturation: f32,
+) {
+    if !image_data.len().is_multiple_of(4) {
+        return;
+    }
+
+    let decode = srgb_decode_lut();
+    for_each_pixel(image_data, |px| {
+        let rgb = [px[0], px[1], px[2]].map(|c| decode[c as usize]);
+        let out = adjust_rgb(rgb, brightness, contrast, saturation);
+        px[0] = to_u8(linear_to_srgb(out[0].clamp(0.0, 1.0)));
+        px[1] = to_u8(linear_to_srgb(out[1].clamp(0.0, 1.0)));
+        px[2] = to_u8(linear_to_srgb(out[2].clamp(0.0, 1.0)));
+    });
+}
+
+/// [`apply_filters`] for premultiplied-alpha RGBA.
+///
+/// Color is divided by alpha before filtering and multiplied back afterwards,
+/// so anti-aliased edges aren't darkened by contrast. Fully transparent pixels
+/// are skipped.
+#[wasm_bindgen]
+pub fn apply_filters_premultiplied(
+    image_data: &mut [u8],
+    brightness: f32,
+    contrast: f32,
+    saturation: f32,
+) {
+    if !image_data.len().is_multiple_of(4) {
+        return;
+    }
+
+    for_each_pixel(image_data, |px| {
+        if px[3] == 0 {
+            return;
+        }
+        let alpha = px[3] as f32 / 255.0;
+        let rgb = [px[0], px[1], px[2]].map(|c| (c as f32 / 255.0 / alpha).min(1.0));
+        let out = adjust_rgb(rgb, brightness, contrast, saturation);
+        px[0] = to_u8(out[0].clamp(0.0, 1.0) * alpha);
+        px[1] = to_u8(out[1].clamp(0.0, 1.0) * alpha);
+        px[2] = to_u8(out[2].clamp(0.0, 1.0) * alpha);
+    });
+}
+
+/// [`apply_filters`] for 16-bit-per-channel RGBA, keeping full precision.
+///
+/// Channels are normalized by 65535 and clamped back to `u16`. Alpha is
+/// untouched. Buffers whose length isn't a multiple of 4 are ignored.
+#[wasm_bindgen]
+pub fn apply_filters_u16(image_data: &mut [u16], brightness: f32, contrast: f32, saturation: f32) {
+    if !image_data.len().is_multiple_of(4) {
+        return;
+    }
+
+    for_each_pixel(image_data, |px| {
+        let rgb = [px[0], px[1], px[2]].map(|c| c as f32 / 65535.0);
+        let out = adjust_rgb(rgb, brightness, contrast, saturation);
+        for c in 0..3 {
+            px[c] = (out[c] * 65535.0).round().clamp(0.0, 65535.0) as u16;
+        }
+    });
+}
+
+/// Out-of-place [`apply_filters`]: returns a filtered copy and leaves the input untouched.
+///
+/// Buffers whose length isn't a multiple of 4 come back as an unmodified copy.
+#[wasm_bindgen]
+pub fn apply_filters_copy(
+    image_data: &[u8],
+    brightness: f32,
+    contrast: f32,
+    saturation: f32,
+) -> Vec<u8> {
+    let mut out = image_data.to_vec();
+    apply_filters(&mut out, brightness, contrast, saturation);
+    out
+}
+
+/// Apply brightness, contrast and saturation weighted by a per-pixel mask.
+///
+/// `image_data`: RGBA pixels, modified in place
+/// `mask`: one byte per pixel; 0 keeps the original, 255 applies the full effect
+/// `width`, `height`: image dimensions
+///
+/// Filter parameters match [`apply_filters`]. Returns early if either buffer
+/// doesn't match `width * height`.
+#[wasm_bindgen]
+pub fn apply_filters_masked(
+    image_data: &mut [u8],
+    mask: &[u8],
+    width: u32,
+    height: u32,
+    brightness: f32,
+    contrast: f32,
+    saturation: f32,
+) {
+    let pixel_count = width as usize * height as usize;
+    if image_data.len() != pixel_count * 4 || mask.len() != pixel_count {
+        return;
+    }
+
+    let width = width as usize;
+    for_each_row(image_data, width, |y, row| {
+        let mask_row = &mask[y * width..(y + 1) * width];
+        for (px, &m) in row.chunks_exact_mut(4).zip(mask_row) {
+            if m == 0 {
+                continue;
+            }
+            let t = m as f32 / 255.0;
+            let rgb = [px[0], px[1], px[2]].map(|c| c as f32 / 255.0);
+            let out = adjust_rgb(rgb, brightness, contrast, saturation);
+            for c in 0..3 {
+                px[c] = to_u8(rgb[c] + (out[c].clamp(0.0, 1.0) - rgb[c]) * t);
+            }
+        }
+    });
+}
+
+/// Apply brightness, contrast and saturation inside a rectangle only.
+///
+/// `image_data`: RGBA pixels, modified in place
+/// `width`, `height`: image dimensions
+/// `x`, `y`, `w`, `h`: rectangle to filter, clamped to the image bounds
+///
+/// Filter parameters match [`apply_filters`]. Returns early if `image_data`
+/// doesn't match `width * height * 4` or the clamped rectangle is empty.
+#[allow(clippy::too_many_arguments)] // wasm-bindgen exports a flat ABI, so options can't be grouped into a struct
+#[wasm_bindgen]
+pub fn apply_filters_region(
+    image_data: &mut [u8],
+    width: u32,
+    height: u32,
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+    brightness: f32,
+    contrast: f32,
+    saturation: f32,
+) {
+    if image_data.len() != width as usize * height as usize * 4 || x >= width || y >= height {
+        return;
+    }
+    let w = w.min(width - x) as usize;
+    let h = h.min(height - y) as usize;
+    let x = x as usize;
+
+    for row in image_data
+        .chunks_exact_mut(width as usize * 4)
+        .skip(y as usize)
+        .take(h)
+    {
+        apply_filters(
+            &mut row[x * 4..(x + w) * 4],
+            brightness,
+            contrast,
+            saturation,
+        );
+    }
+}
+
+/// Replace every pixel close to one color with another, opaque color.
+///
+/// `image_data`: RGBA pixels, modified in place
+/// `from`: color to find, packed as `0xRRGGBB`
+/// `to`: replacement color, packed as `0xRRGGBB`
+/// `tolerance`: max Euclidean RGB distance from `from` (0 = exact match)
+///
+/// Alpha is preserved, unlike chroma keying which writes transparency.
+#[wasm_bindgen]
+pub fn replace_color(image_data: &mut [u8], from: u32, to: u32, tolerance: u32) {
+    if !image_data.len().is_multiple_of(4) {
+        return;
+    }
+
+    let (fr, fg, fb) = unpack_rgb(from);
+    let (tr, tg, tb) = unpack_rgb(to);
+    let max_dist_sq = tolerance as u64 * tolerance as u64;
+
+    for_each_pixel(image_data, |px| {
+        let dr = px[0] as i32 - fr as i32;
+        let dg = px[1] as i32 - fg as i32;
+        let db = px[2] as i32 - fb as i32;
+        let dist_sq = (dr * dr + dg * dg + db * db) as u64;
+
+        if dist_sq <= max_dist_sq {
+            px[0] = tr;
+            px[1] = tg;
+            px[2] = tb;
+        }
+    });
+}
+
+/// Black and white points after clipping `clip_percent` (0-50) of the counted
+/// pixels at each end of a histogram. `None` when the range collapses.
+fn clip_points(histogram: &[u32; 256], clip_percent: f32) -> Option<(usize, usize)> {
+    let total: u64 = histogram.iter().map(|&c| c as u64).sum();
+    let clip = (total as f32 * clip_percent.clamp(0.0, 50.0) / 100.0) as u64;
+
+    let mut acc = 0u64;
+    let black = histogram.iter().position(|&count| {
+        acc += count as u64;
+        acc > clip
+    })?;
+
+    acc = 0;
+    let white = 255
+        - histogram.iter().rev().position(|&count| {
+            acc += count as u64;
+            acc > clip
+        })?;
+
+    (white > black).then_some((black, white))
+}
+
+/// Stretch contrast so the darkest and brightest luma values span 0..255.
+///
+/// `image_data`: RGBA pixels, modified in place
+/// `clip_percent`: share of pixels (0-50) ignored at each end of the luma
+/// histogram when picking the black and white points, e.g. 0.5
+///
+/// Every RGB channel is remapped with the same linear stretch. Alpha is untouched.
+#[wasm_bindgen]
+pub fn auto_contrast(image_data: &mut [u8], clip_percent: f32) {
+    if !image_data.len().is_multiple_of(4) || image_data.is_empty() {
+        return;
+    }
+
+    let histogram = histograms(image_data, false)[3];
+    let Some((black, white)) = clip_points(&histogram, clip_percent) else {
+        return;
+    };
+    let lut = levels_lut(black as f32 / 255.0, white as f32 / 255.0, 1.0, 0.0, 1.0);
+
+    for_each_pixel(image_data, |px| {
+        px[0] = lut[px[0] as usize];
+        px[1] = lut[px[1] as usize];
+        px[2] = lut[px[2] as usize];
+    });
+}
+
+/// Pixels darker or brighter than these bounds are skipped when measuring a cast.
+const WB_NEAR_BLACK: u8 = 16;
+const WB_NEAR_WHITE: u8 = 239;
+
+/// Neutralize a color cast using the gray-world assumption.
+///
+/// `image_data`: RGBA pixels, modified in place
+///
+/// Each channel is scaled so the R, G and B averages match their common mean.
+/// Fully transparent, near-black and near-white pixels are left out of the
+/// averages so clipped regions don't skew the correction. Alpha is untouched.
+#[wasm_bindgen]
+pub fn auto_white_balance(image_data: &mut [u8]) {
+    if !image_data.len().is_multiple_of(4) {
+        return;
+    }
+
+    let mut sums = [0u64; 3];
+    let mut count = 0u64;
+    for px in image_data.chunks_exact(4) {
+        if px[3] == 0 {
+            continue;
+        }
+        let max = px[0].max(px[1]).max(px[2]);
+        let min = px[0].min(px[1]).min(px[2]);
+        if max < WB_NEAR_BLACK || min > WB_NEAR_WHITE {
+            continue;
+        }
+        sums[0] += px[0] as u64;
+        sums[1] += px[1] as u64;
+        sums[2] += px[2] as u64;
+        count += 1;
+    }
+
+    if count == 0 || sums.contains(&0) {
+        return;
+    }
+
+    let avg = sums.map(|s| s as f32 / count as f32);
+    let gray = (avg[0] + avg[1] + avg[2]) / 3.0;
+    let gains = avg.map(|a| gray / a);
+
+    for_each_pixel(image_data, |px| {
+        for c in 0..3 {
+            px[c] = (px[c] as f32 * gains[c]).round().clamp(0.0, 255.0) as u8;
+        }
+    });
+}
+
+/// Linear RGB to LMS cone space (Viénot, Brettel & Mollon 1999).
+const RGB_TO_LMS: [[f32; 3]; 3] = [
+    [17.8824, 43.5161, 4.11935],
+    [3.45565, 27.1554, 3.86714],
+    [0.0299566, 0.184309, 1.46709],
+];
+
+const LMS_TO_RGB: [[f32; 3]; 3] = [
+    [0.080_944_45, -0.130_504_41, 0.116_721_07],
+    [-0.010_248_53, 0.054_019_33, -0.113_614_71],
+    [-0.000_365_297, -0.004_121_615, 0.693_511_4],
+];
+
+/// Dichromat projections in LMS space, indexed by `simulate_color_blindness` mode.
+const DICHROMACY: [[[f32; 3]; 3]; 3] = [
+    // Protanopia: missing L cones
+    [[0.0, 2.02344, -2.52581], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+    // Deuteranopia: missing M cones
+    [[1.0, 0.0, 0.0], [0.494207, 0.0, 1.24827], [0.0, 0.0, 1.0]],
+    // Tritanopia: missing S cones, projected onto the plane through white and red
+    [
+        [1.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0],
+        [-0.012_245, 0.072_035, 0.0],
+    ],
+];
+
+fn mat3_mul(a: &[[f32; 3]; 3], b: &[[f32; 3]; 3]) -> [[f32; 3]; 3] {
+    std::array::from_fn(|i| std::array::from_fn(|j| (0..3).map(|k| a[i][k] * b[k][j]).sum()))
+}
+
+/// Simulate how an image appears to a viewer with dichromatic color blindness.
+///
+/// `image_data`: RGBA pixels, modified in place
+/// `mode`: 0 = protanopia, 1 = deuteranopia, 2 = tritanopia; other values are ignored
+///
+/// Pixels are decoded to linear light, projected in LMS cone space, converted
+/// back and clamped. Alpha is untouched.
+#[wasm_bindgen]
+pub fn simulate_color_blindness(image_data: &mut [u8], mode: u32) {
+    if !image_data.len().is_multiple_of(4) {
+        return;
+    }
+    let Some(deficiency) = DICHROMACY.get(mode as usize) else {
+        return;
+    };
+
+    let m = mat3_mul(&LMS_TO_RGB, &mat3_mul(deficiency, &RGB_TO_LMS));
+    let decode = srgb_decode_lut();
+
+    for_each_pixel(image_data, |px| {
+        let rgb = [px[0], px[1], px[2]].map(|c| decode[c as usize]);
+        for (c, row) in m.iter().enumerate() {
+            let v = row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2];
+            px[c] = to_u8(linear_to_srgb(v.clamp(0.0, 1.0)));
+        }
+    });
+}
+
+/// Approximate RGB color (0..1) of a blackbody radiator, after Tanner Helland's fit.
+fn blackbody_rgb(kelvin: f32) -> [f32; 3] {
+    let t = kelvin / 100.0;
+    let r = if t <= 66.0 {
+        255.0
+    } else {
+        329.698_73 * (t - 60.0).powf(-0.133_204_76)
+    };
+    let g = if t <= 66.0 {
+        99.470_8 * t.ln() - 161.119_57
+    } else {
+        288.122_16 * (t - 60.0).powf(-0.075_514_85)
+    };
+    let b = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.517_73 * (t - 10.0).ln() - 305.044_8
+    };
+    [r, g, b].map(|c| c.clamp(0.0, 255.0) / 255.0)
+}
+
+/// Tint an image toward the color of a light source at a given temperature.
+///
+/// `image_data`: RGBA pixels, modified in place
+/// `kelvin`: color temperature, clamped to 1000..12000 K. Lower values warm the
+/// image, higher values cool it, and 6500 K (D65 white) leaves it unchanged.
+///
+/// Channel gains are the blackbody color at `kelvin` divided by the blackbody
+/// color at 6500 K. Alpha is untouched.
+#[wasm_bindgen]
+pub fn white_balance_kelvin(image_data: &mut [u8], kelvin: f32) {
+    if !image_data.len().is_multiple_of(4) {
+        return;
+    }
+
+    let target = blackbody_rgb(kelvin.clamp(1000.0, 12000.0));
+    let reference = blackbody_rgb(6500.0);
+
+    let luts: [[u8; 256]; 3] = std::array::from_fn(|c| {
+        let gain = target[c] / reference[c];
+        std::array::from_fn(|i| (i as f32 * gain).round().clamp(0.0, 255.0) as u8)
+    });
+
+    for_each_pixel(image_data, |px| {
+        for (c, lut) in luts.iter().enumerate() {
+            px[c] = lut[px[c] as usize];
+        }
+    });
+}
+
+/// How far [`decontaminate_edges`] searches for transparent and opaque pixels.
+const DECONTAMINATE_RADIUS: usize = 8;
+
+/// Remove background color bleed from semi-transparent edge pixels.
+///
+/// `image_data`: RGBA pixels, modified in place
+/// `width`, `height`: image dimensions
+///
+/// Each partially transparent pixel is treated as `alpha * fg + (1 - alpha) * bg`.
+/// The background is the average of the nearest fully transparent pixels
+/// (keyed-out pixels keep their original RGB) and the solved foreground is
+/// clamped to the range of the nearest fully opaque pixels so noise can't
+/// overshoot. Both are searched for in growing rings up to 8 pixels out, which
+/// covers soft mattes several pixels wide. Pixels without both within reach are
+/// left alone, since there's nothing to estimate the mix from. Alpha is untouched.
+#[wasm_bindgen]
+pub fn decontaminate_edges(image_data: &mut [u8], width: u32, height: u32) {
+    let (w, h) = (width as usize, height as usize);
+    if image_data.len() != w * h * 4 {
+        return;
+    }
+
+    let src = image_data.to_vec();
+    let bg_dist = alpha_distance(&src, w, h, 0);
+    let fg_dist = alpha_distance(&src, w, h, 255);
+    for_each_row(image_data, w, |y, row| {
+        for x in 0..w {
+            let i = (y * w + x) * 4;
+            let alpha = src[i + 3];
+            let (bg_d, fg_d) = (bg_dist[y * w + x] as usize, fg_dist[y * w + x] as usize);
+            if alpha == 0
+                || alpha == 255
+                || bg_d > DECONTAMINATE_RADIUS
+                || fg_d > DECONTAMINATE_RADIUS
+            {
+                continue;
+            }
+
+            let mut bg_sum = [0u32; 3];
+            let mut bg_count = 0;
+            for (nx, ny) in ring(x, y, bg_d, w, h) {
+                let n = &src[(ny * w + nx) * 4..(ny * w + nx) * 4 + 4];
+                if n[3] == 0 {
+                    for c in 0..3 {
+                        bg_sum[c] += n[c] as u32;
+                    }
+                    bg_count += 1;
+                }
+            }
+            let bg = bg_sum.map(|v| v as f32 / bg_count as f32);
+
+            let mut fg_min = [255u8; 3];
+            let mut fg_max = [0u8; 3];
+            for (nx, ny) in ring(x, y, fg_d, w, h) {
+                let n = &src[(ny * w + nx) * 4..(ny * w + nx) * 4 + 4];
+                if n[3] == 255 {
+                    for c in 0..3 {
+                        fg_min[c] = fg_min[c].min(n[c]);
+                        fg_max[c] = fg_max[c].max(n[c]);
+                    }
+                }
+            }
+
+            let a = alpha as f32 / 255.0;
+            for c in 0..3 {
+                let fg = (src[i + c] as f32 - (1.0 - a) * bg[c]) / a;
+                row[x * 4 + c] = fg.round().clamp(fg_min[c] as f32, fg_max[c] as f32) as u8;
+            }
+        }
+    });
+}
+
+/// Chebyshev distance from each pixel to the nearest one with alpha `target`,
+/// saturating at one past [`DECONTAMINATE_RADIUS`].
+///
+/// Two raster sweeps, each taking the distances already settled on one side.
+fn alpha_distance(src: &[u8], w: usize, h: usize, target: u8) -> Vec<u8> {
+    let far = DECONTAMINATE_RADIUS as u8 + 1;
+    let mut dist: Vec<u8> = src
+        .chunks_exact(4)
+        .map(|px| if px[3] == target { 0 } else { far })
+        .collect();
+
+    for y in 0..h {
+        for x in 0..w {
+            let mut d = dist[y * w + x];
+            if x > 0 {
+                d = d.min(dist[y * w + x - 1] + 1);
+            }
+            if y > 0 {
+                for nx in x.saturating_sub(1)..=(x + 1).min(w - 1) {
+                    d = d.min(dist[(y - 1) * w + nx] + 1);
+                }
+            }
+            dist[y * w + x] = d;
+        }
+    }
+    for y in (0..h).rev() {
+        for x in (0..w).rev() {
+            let mut d = dist[y * w + x];
+            if x + 1 < w {
+                d = d.min(dist[y * w + x + 1] + 1);
+            }
+            if y + 1 < h {
+                for nx in x.saturating_sub(1)..=(x + 1).min(w - 1) {
+                    d = d.min(dist[(y + 1) * w + nx] + 1);
+                }
+            }
+            dist[y * w + x] = d;
+        }
+    }
+    dist
+}
+
+/// In-bounds pixels at Chebyshev distance `d` from `(x, y)`.
+fn ring(x: usize, y: usize, d: usize, w: usize, h: usize) -> impl Iterator<Item = (usize, usize)> {
+    let (x, y, d) = (x as isize, y as isize, d as isize);
+    let rows = (-d..=d).flat_map(move |t| [(x + t, y - d), (x + t, y + d)]);
+    let columns = (1 - d..d).flat_map(move |t| [(x - d, y + t), (x + d, y + t)]);
+    rows.chain(columns)
+        .filter(move |&(nx, ny)| nx >= 0 && ny >= 0 && nx < w as isize && ny < h as isize)
+        .map(|(nx, ny)| (nx as usize, ny as usize))
+}
+
+/// Lookup table snapping 0..255 onto `levels` evenly spaced values.
+fn posterize_lut(levels: u32) -> [u8; 256] {
+    let steps = (levels.clamp(2, 256) - 1) as f32;
+    std::array::from_fn(|i| ((i as f32 / 255.0 * steps).round() * 255.0 / steps).round() as u8)
+}
+
+/// Cartoon effect: posterized colors with darkened Sobel edges.
+///
+/// `image_data`: RGBA pixels, modified in place
+/// `width`, `height`: image dimensions
+/// `color_levels`: bands per channel (2-256); values below 2 skip posterizing
+/// `edge_strength`: 0 = no outlines, 1 = full black on the strongest edges
+///
+/// Edges are detected on the original image so posterization bands don't add
+/// outlines of their own. Alpha is untouched.
+#[wasm_bindgen]
+pub fn cartoonize(
+    image_data: &mut [u8],
+    width: u32,
+    height: u32,
+    color_levels: u32,
+    edge_strength: f32,
+) {
+    let (w, h) = (width as usize, height as usize);
+    if image_data.len() != w * h * 4 {
+        return;
+    }
+
+    let edge_strength = edge_strength.clamp(0.0, 1.0);
+    let edges = if edge_strength > 0.0 {
+        sobel_magnitude(image_data, w, h)
+    } else {
+        Vec::new()
+    };
+    let lut = (color_levels >= 2).then(|| posterize_lut(color_levels));
+
+    for_each_row(image_data, w, |y, row| {
+        for (x, px) in row.chunks_exact_mut(4).enumerate() {
+            if let Some(lut) = &lut {
+                for v in &mut px[..3] {
+                    *v = lut[*v as usize];
+                }
+            }
+            if let Some(&edge) = edges.get(y * w + x) {
+                let keep = 1.0 - edge_strength * edge.min(1.0);
+                for v in &mut px[..3] {
+                    *v = (*v as f32 * keep).round() as u8;
+                }
+            }
+        }
+    });
+}
+
+/// Apply a gamma curve to the RGB channels.
+///
+/// `image_data`: RGBA pixels, modified in place
+/// `gamma`: each normalized channel is raised to `1 / gamma`; 1 is a no-op,
+/// values above 1 brighten midtones and values below 1 darken them. Non-positive
+/// values are ignored.
+///
+/// Uses a 256-entry lookup table. Alpha is untouched. Buffers whose length isn't
+/// a multiple of 4 are ignored.
+#[wasm_bindgen]
+pub fn apply_gamma(image_data: &mut [u8], gamma: f32) {
+    if !image_data.len().is_multiple_of(4) || gamma <= 0.0 || gamma == 1.0 {
+        return;
+    }
+
+    let exponent = 1.0 / gamma;
+    let lut: [u8; 256] = std::array::from_fn(|i| to_u8((i as f32 / 255.0).powf(exponent)));
+
+    for_each_pixel(image_data, |px| {
+        px[0] = lut[px[0] as usize];
+        px[1] = lut[px[1] as usize];
+        px[2] = lut[px[2] as usize];
+    });
+}
+
+/// Classic sepia tone matrix, shared with the WebGL color-correction shader.
+const SEPIA: [[f32; 3]; 3] = [
+    [0.393, 0.769, 0.189],
+    [0.349, 0.686, 0.168],
+    [0.272, 0.534, 0.131],
+];
+
+/// Blend toward a warm sepia tone.
+///
+/// `image_data`: RGBA pixels, modified in place
+/// `intensity`: 0 = original, 1 = full sepia (clamped)
+///
+/// Alpha is untouched.
+#[wasm_bindgen]
+pub fn apply_sepia(image_data: &mut [u8], intensity: f32) {
+    let intensity = intensity.clamp(0.0, 1.0);
+    if !image_data.len().is_multiple_of(4) || intensity == 0.0 {
+        return;
+    }
+
+    for_each_pixel(image_data, |px| {
+        let rgb = [px[0], px[1], px[2]].map(|c| c as f32);
+        for (c, row) in SEPIA.iter().enumerate() {
+            let sepia = row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2];
+            px[c] = (rgb[c] + (sepia - rgb[c]) * intensity)
+                .round()
+                .clamp(0.0, 255.0) as u8;
+        }
+    });
+}
+
+/// Map luma onto a gradient between two colors.
+///
+/// `image_data`: RGBA pixels, modified in place
+/// `shadow_rgb`: RGB triplet used for black
+/// `highlight_rgb`: RGB triplet used for white
+///
+/// Luma uses the same BT.709 weights as [`apply_filters`]. Alpha is untouched.
+/// Returns early unless both colors are exactly 3 bytes.
+#[wasm_bindgen]
+pub fn apply_duotone(image_data: &mut [u8], shadow_rgb: &[u8], highlight_rgb: &[u8]) {
+    if !image_data.len().is_multiple_of(4) || shadow_rgb.len() != 3 || highlight_rgb.len() != 3 {
+        return;
+    }
+
+    let lut: [[u8; 3]; 256] = std::array::from_fn(|i| {
+        let t = i as f32 / 255.0;
+        std::array::from_fn(|c| {
+            let (lo, hi) = (shadow_rgb[c] as f32, highlight_rgb[c] as f32);
+            (lo + (hi - lo) * t).round() as u8
+        })
+    });
+
+    for_each_pixel(image_data, |px| {
+        let mapped = lut[luma_u8(px[0], px[1], px[2]) as usize];
+        px[..3].copy_from_slice(&mapped);
+    });
+}
+
+/// Adjust exposure in photographic stops.
+///
+/// `image_data`: RGBA pixels, modified in place
+/// `stops`: linear-light gain of `2^stops`; +1 doubles the light, -1 halves it
+///
+/// Channels are decoded from sRGB to linear light before the gain and
+/// re-encoded afterwards, so each stop looks like a real exposure change. Zero
+/// stops leave the buffer untouched. Alpha is untouched.
+#[wasm_bindgen]
+pub fn apply_exposure(image_data: &mut [u8], stops: f32) {
+    if !image_data.len().is_multiple_of(4) || stops == 0.0 {
+        return;
+    }
+
+    let gain = stops.exp2();
+    let decode = srgb_decode_lut();
+    let lut: [u8; 256] =
+        std::array::from_fn(|i| to_u8(linear_to_srgb((decode[i] * gain).min(1.0))));
+
+    for_each_pixel(image_data, |px| {
+        px[0] = lut[px[0] as usize];
+        px[1] = lut[px[1] as usize];
+        px[2] = lut[px[2] as usize];
+    });
+}
+
+/// Strength of the white balance channel gains at full temperature or tint.
+const WB_GAIN: f32 = 0.2;
+
+/// Correct white balance with relative temperature and tint.
+///
+/// `image_data`: RGBA pixels, modified in place
+/// `temperature`: -1 (cool) to 1 (warm), clamped
+/// `tint`: -1 (magenta) to 1 (green), clamped; same sign as the WebGL shader
+///
+/// Channels are multiplied by fixed gains, then rounded and clamped:
+/// red by `1 + 0.2 * temperature`, blue by `1 - 0.2 * temperature` and green by
+/// `1 + 0.2 * tint`. Alpha is untouched.
+#[wasm_bindgen]
+pub fn apply_white_balance(image_data: &mut [u8], temperature: f32, tint: f32) {
+    if !image_data.len().is_multiple_of(4) {
+        return;
+    }
+
+    let temperature = temperature.clamp(-1.0, 1.0);
+    let tint = tint.clamp(-1.0, 1.0);
+    let gains = [
+        1.0 + WB_GAIN * temperature,
+        1.0 + WB_GAIN * tint,
+        1.0 - WB_GAIN * temperature,
+    ];
+    let luts: [[u8; 256]; 3] = std::array::from_fn(|c| {
+        std::array::from_fn(|i| (i as f32 * gains[c]).round().clamp(0.0, 255.0) as u8)
+    });
+
+    for_each_pixel(image_data, |px| {
+        for (c, lut) in luts.iter().enumerate() {
+            px[c] = lut[px[c] as usize];
+        }
+    });
+}
+
+/// Saturation boost that favors muted pixels over already vivid ones.
+///
+/// `image_data`: RGBA pixels, modified in place
+/// `amount`: 0 = no-op; positive boosts, negative mutes
+///
+/// Each pixel's saturation multiplier is `1 + amount * (1 - s)`, where `s` is its
+/// current HSV saturation, applied as a blend away from BT.709 gray like
+/// [`apply_filters`]. Alpha is untouched.
+#[wasm_bindgen]
+pub fn apply_vibrance(image_data: &mut [u8], amount: f32) {
+    if !image_data.len().is_multiple_of(4) || amount == 0.0 {
+        return;
+    }
+
+    for_each_pixel(image_data, |px| {
+        let max = px[0].max(px[1]).max(px[2]);
+        if max == 0 {
+            return;
+        }
+        let min = px[0].min(px[1]).min(px[2]);
+        let sat = (max - min) as f32 / max as f32;
+        let factor = 1.0 + amount * (1.0 - sat);
+
+        let gray = luma(px[0], px[1], px[2]);
+        for v in &mut px[..3] {
+            *v = (gray + (*v as f32 - gray) * factor)
+                .round()
+                .clamp(0.0, 255.0) as u8;
+        }
+    });
+}
+
+/// Lookup table for a levels remap; inputs are normalized to 0..1.
+fn levels_lut(
+    in_black: f32,
+    in_white: f32,
+    gamma: f32,
+    out_black: f32,
+    out_white: f32,
+) -> [u8; 256] {
+    let exponent = 1.0 / gamma;
+    std::array::from_fn(|i| {
+        let t = ((i as f32 / 255.0 - in_black) / (in_white - in_black)).clamp(0.0, 1.0);
+        to_u8(out_black + t.powf(exponent) * (out_white - out_black))
+    })
+}
+
+/// Photoshop-style levels: remap input black/white points with a midtone gamma.
+///
+/// `image_data`: RGBA pixels, modified in place
+/// `in_black`, `in_white`: input range mapped to the full output range (0..1)
+/// `gamma`: midtone gamma; 1 = linear, above 1 brightens midtones
+/// `out_black`, `out_white`: output range (0..1)
+///
+/// Each channel is normalized as `(c - in_black) / (in_white - in_black)`,
+/// clamped, raised to `1 / gamma`, then scaled into `out_black..out_white`.
+/// Returns without modifying anything if `in_white <= in_black` or `gamma <= 0`.
+/// Alpha is untouched.
+#[wasm_bindgen]
+pub fn apply_levels(
+    image_data: &mut [u8],
+    in_black: f32,
+    in_white: f32,
+    gamma: f32,
+    out_black: f32,
+    out_white: f32,
+) {
+    if !image_data.len().is_multiple_of(4) || in_white <= in_black || gamma <= 0.0 {
+        return;
+    }
+
+    let lut = levels_lut(in_black, in_white, gamma, out_black, out_white);
+    for_each_pixel(image_data, |px| {
+        px[0] = lut[px[0] as usize];
+        px[1] = lut[px[1] as usize];
+        px[2] = lut[px[2] as usize];
+    });
+}
+
+/// Apply a 3D color lookup table with trilinear interpolation.
+///
+/// `image_data`: RGBA pixels, modified in place
+/// `lut`: `size^3` RGB triplets in 0..1, red varying fastest then green then blue,
+/// which is the order `.cube` files list them in
+/// `size`: lattice points per axis (at least 2)
+///
+/// Returns early if `lut.len() != size * size * size * 3`. Alpha is untouched.
+#[wasm_bindgen]
+pub fn apply_lut3d(image_data: &mut [u8], lut: &[f32], size: u32) {
+    let n = size as usize;
+    if !image_data.len().is_multiple_of(4) || n < 2 || lut.len() != n * n * n * 3 {
+        return;
+    }
+
+    let max = (n - 1) as f32;
+    let at = |r: usize, g: usize, b: usize| {
+        let i = ((b * n + g) * n + r) * 3;
+        [lut[i], lut[i + 1], lut[i + 2]]
+    };
+
+    for_each_pixel(image_data, |px| {
+        let pos = [px[0], px[1], px[2]].map(|c| (c as f32 / 255.0).clamp(0.0, 1.0) * max);
+        let lo = pos.map(|p| (p.floor() as usize).min(n - 2));
+        let [fr, fg, fb] = [0, 1, 2].map(|c| pos[c] - lo[c] as f32);
+        let [r0, g0, b0] = lo;
+
+        let lerp = |a: [f32; 3], b: [f32; 3], t: f32| -> [f32; 3] {
+            std::array::from_fn(|c| a[c] + (b[c] - a[c]) * t)
+        };
+        let c00 = lerp(at(r0, g0, b0), at(r0 + 1, g0, b0), fr);
+        let c10 = lerp(at(r0, g0 + 1, b0), at(r0 + 1, g0 + 1, b0), fr);
+        let c01 = lerp(at(r0, g0, b0 + 1), at(r0 + 1, g0, b0 + 1), fr);
+        let c11 = lerp(at(r0, g0 + 1, b0 + 1), at(r0 + 1, g0 + 1, b0 + 1), fr);
+        let out = lerp(lerp(c00, c10, fg), lerp(c01, c11, fg), fb);
+
+        px[0] = to_u8(out[0]);
+        px[1] = to_u8(out[1]);
+        px[2] = to_u8(out[2]);
+    });
+}
+
+/// Sample a monotone cubic (Fritsch-Carlson) curve through the points into a LUT.
+///
+/// `xs` must be strictly increasing with at least two points. Inputs outside
+/// the first/last point hold the end values.
+fn curve_lut(xs: &[f32], ys: &[f32]) -> [u8; 256] {
+    let n = xs.len();
+    let slopes: Vec<f32> = (0..n - 1)
+        .map(|i| (ys[i + 1] - ys[i]) / (xs[i + 1] - xs[i]))
+        .collect();
+
+    let mut tangents = vec![0.0f32; n];
+    tangents[0] = slopes[0];
+    tangents[n - 1] = slopes[n - 2];
+    for i in 1..n - 1 {
+        tangents[i] = if slopes[i - 1] * slopes[i] <= 0.0 {
+            0.0
+        } else {
+            (slopes[i - 1] + slopes[i]) / 2.0
+        };
+    }
+    for i in 0..n - 1 {
+        if slopes[i] == 0.0 {
+            tangents[i] = 0.0;
+            tangents[i + 1] = 0.0;
+            continue;
+        }
+        let a = tangents[i] / slopes[i];
+        let b = tangents[i + 1] / slopes[i];
+        let h = a * a + b * b;
+        if h > 9.0 {
+            let t = 3.0 / h.sqrt();
+            tangents[i] = t * a * slopes[i];
+            tangents[i + 1] = t * b * slopes[i];
+        }
+    }
+
+    std::array::from_fn(|i| {
+        let x = i as f32 / 255.0;
+        let y = if x <= xs[0] {
+            ys[0]
+        } else if x >= xs[n - 1] {
+            ys[n - 1]
+        } else {
+            let k = xs.partition_point(|&v| v <= x) - 1;
+            let h = xs[k + 1] - xs[k];
+            let t = (x - xs[k]) / h;
+            let (t2, t3) = (t * t, t * t * t);
+            (2.0 * t3 - 3.0 * t2 + 1.0) * ys[k]
+                + (t3 - 2.0 * t2 + t) * h * tangents[k]
+                + (-2.0 * t3 + 3.0 * t2) * ys[k + 1]
+                + (t3 - t2) * h * tangents[k + 1]
+        };
+        to_u8(y)
+    })
+}
+
+/// Apply a tone curve defined by control points.
+///
+/// `image_data`: RGBA pixels, modified in place
+/// `channel`: 0 = red, 1 = green, 2 = blue, 3 = all three
+/// `xs`, `ys`: control points in 0..1; `xs` must be strictly increasing
+///
+/// The points are joined with monotone cubic interpolation, so the curve never
+/// overshoots between them, and sampled into a 256-entry lookup table. Returns
+/// without modifying anything if the channel is unknown, fewer than two points
+/// are given, the lengths differ or `xs` isn't sorted. Alpha is untouched.
+#[wasm_bindgen]
+pub fn apply_curve(image_data: &mut [u8], channel: u8, xs: &[f32], ys: &[f32]) {
+    if !image_data.len().is_multiple_of(4)
+        || channel > 3
+        || xs.len() < 2
+        || xs.len() != ys.len()
+        || xs.windows(2).any(|w| w[0] >= w[1])
+    {
+        return;
+    }
+
+    let lut = curve_lut(xs, ys);
+    let channels = if channel == 3 {
+        0..3
+    } else {
+        channel as usize..channel as usize + 1
+    };
+
+    for_each_pixel(image_data, |px| {
+        for v in &mut px[channels.clone()] {
+            *v = lut[*v as usize];
+        }
+    });
+}
+
+/// Quantize each RGB channel into a fixed number of bands.
+///
+/// `image_data`: RGBA pixels, modified in place
+/// `levels`: bands per channel (2-255); 0 and 1 are treated as a no-op
+///
+/// Alpha is untouched.
+#[wasm_bindgen]
+pub fn apply_posterize(image_data: &mut [u8], levels: u8) {
+    if !image_data.len().is_multiple_of(4) || levels < 2 {
+        return;
+    }
+
+    let lut = posterize_lut(levels as u32);
+    for_each_pixel(image_data, |px| {
+        px[0] = lut[px[0] as usize];
+        px[1] = lut[px[1] as usize];
+        px[2] = lut[px[2] as usize];
+    });
+}
+
+/// Convert to pure black and white by comparing luma against a cutoff.
+///
+/// `image_data`: RGBA pixels, modified in place
+/// `cutoff`: normalized BT.709 luma (0..1) at or above which a pixel turns white
+///
+/// Alpha is untouched.
+#[wasm_bindgen]
+pub fn apply_threshold(image_data: &mut [u8], cutoff: f32) {
+    if !image_data.len().is_multiple_of(4) {
+        return;
+    }
+
+    let cutoff = cutoff * 255.0;
+    for_each_pixel(image_data, |px| {
+        let v = if luma(px[0], px[1], px[2]) >= cutoff {
+            255
+        } else {
+            0
+        };
+        px[..3].fill(v);
+    });
+}
+
+/// Darken the image toward its corners.
+///
+/// `image_data`: RGBA pixels, modified in place
+/// `width`, `height`: image dimensions
+/// `strength`: darkening at full falloff, 0 (no-op) to 1 (black)
+/// `radius`: normalized distance where darkening begins; 0 is the center and 1
+/// the corners
+/// `feather`: width of the smoothstep transition past `radius`; 0 gives a hard edge
+///
+/// Returns early unless `width * height * 4 == image_data.len()`. Alpha is untouched.
+#[wasm_bindgen]
+pub fn apply_vignette(
+    image_data: &mut [u8],
+    width: u32,
+    height: u32,
+    strength: f32,
+    radius: f32,
+    feather: f32,
+) {
+    let (w, h) = (width as usize, height as usize);
+    let strength = strength.clamp(0.0, 1.0);
+    if image_data.len() != w * h * 4 || strength == 0.0 {
+        return;
+    }
+
+    let (cx, cy) = (w as f32 / 2.0, h as f32 / 2.0);
+    let half_diagonal = (cx * cx + cy * cy).sqrt();
+    let feather = feather.max(0.0);
+
+    for_each_row(image_data, w, |y, row| {
+        let dy = y as f32 + 0.5 - cy;
+        for (x, px) in row.chunks_exact_mut(4).enumerate() {
+            let dx = x as f32 + 0.5 - cx;
+            let d = (dx * dx + dy * dy).sqrt() / half_diagonal;
+            let falloff = if feather == 0.0 {
+                if d >= radius { 1.0 } else { 0.0 }
+            } else {
+                let t = ((d - radius) / feather).clamp(0.0, 1.0);
+                t * t * (3.0 - 2.0 * t)
+            };
+            let keep = 1.0 - strength * falloff;
+            for v in &mut px[..3] {
+                *v = (*v as f32 * keep).round() as u8;
+            }
+        }
+    });
+}
+
+/// Remix RGB channels through a 3x3 matrix.
+///
+/// `image_data`: RGBA pixels, modified in place
+/// `matrix`: 9 row-major weights; row `i` builds output channel `i` from the
+/// input R, G and B, e.g. three equal rows give a custom grayscale mix
+///
+/// Outputs are clamped. Returns early if `matrix.len() != 9`. Alpha is untouched.
+#[wasm_bindgen]
+pub fn apply_channel_mixer(image_data: &mut [u8], matrix: &[f32]) {
+    if !image_data.len().is_multiple_of(4) || matrix.len() != 9 {
+        return;
+    }
+
+    let m: [[f32; 3]; 3] = std::array::from_fn(|i| std::array::from_fn(|j| matrix[i * 3 + j]));
+    for_each_pixel(image_data, |px| {
+        let rgb = [px[0], px[1], px[2]].map(|c| c as f32);
+        for (c, row) in m.iter().enumerate() {
+            let v = row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2];
+            px[c] = v.round().clamp(0.0, 255.0) as u8;
+        }
+    });
+}
+
+/// Stretch each RGB channel independently to the full range.
+///
+/// `image_data`: RGBA pixels, modified in place
+/// `clip_percent`: share of pixels (0-50) ignored at each end of every channel's
+/// histogram when picking its black and white points, e.g. 0.5
+///
+/// Unlike [`auto_contrast`], channels get their own endpoints, which also
+/// neutralizes color casts. Fully transparent pixels are left out of the
+/// histograms. Channels whose range collapses are left as they are. Alpha is
+/// untouched.
+#[wasm_bindgen]
+pub fn auto_levels(image_data: &mut [u8], clip_percent: f32) {
+    if !image_data.len().is_multiple_of(4) {
+        return;
+    }
+
+    let hist = histograms(image_data, true);
+    let luts: [[u8; 256]; 3] = std::array::from_fn(|c| match clip_points(&hist[c], clip_percent) {
+        Some((black, white)) => {
+            levels_lut(black as f32 / 255.0, white as f32 / 255.0, 1.0, 0.0, 1.0)
+        }
+        None => std::array::from_fn(|i| i as u8),
+    });
+
+    for_each_pixel(image_data, |px| {
+        for (c, lut) in luts.iter().enumerate() {
+            px[c] = lut[px[c] as usize];
+        }
+    });
+}
+
+/// Invert the RGB channels (`255 - c`).
+///
+/// `image_data`: RGBA pixels, modified in place
+///
+/// Alpha is untouched.
+#[wasm_bindgen]
+pub fn apply_invert(image_data: &mut [u8]) {
+    if !image_data.len().is_multiple_of(4) {
+        return;
+    }
+
+    for_each_pixel(image_data, |px| {
+        px[0] = 255 - px[0];
+        px[1] = 255 - px[1];
+        px[2] = 255 - px[2];
+    });
+}
+
+/// Invert only the channel values above a threshold, for a solarized look.
+///
+/// `image_data`: RGBA pixels, modified in place
+/// `threshold`: normalized level (0..1); channels strictly above it are inverted
+///
+/// Alpha is untouched.
+#[wasm_bindgen]
+pub fn apply_solarize(image_data: &mut [u8], threshold: f32) {
+    if !image_data.len().is_multiple_of(4) {
+        return;
+    }
+
+    let threshold = threshold * 255.0;
+    let lut: [u8; 256] = std::array::from_fn(|i| {
+        if i as f32 > threshold {
+            255 - i as u8
+        } else {
+            i as u8
+        }
+    });
+
+    for_each_pixel(image_data, |px| {
+        px[0] = lut[px[0] as usize];
+        px[1] = lut[px[1] as usize];
+        px[2] = lut[px[2] as usize];
+    });
+}
+
+/// BT.709 chroma (Cb, Cr) of an RGB color, each in -0.5..0.5.
+#[inline]
+fn chroma(r: u8, g: u8, b: u8) -> (f32, f32) {
+    let y = luma(r, g, b) / 255.0;
+    let cb = (b as f32 / 255.0 - y) / 1.8556;
+    let cr = (r as f32 / 255.0 - y) / 1.5748;
+    (cb, cr)
+}
+
+/// Key out pixels close to a background color by lowering their alpha.
+///
+/// `image_data`: RGBA pixels, modified in place
+/// `key_rgb`: RGB triplet of the backdrop, e.g. `[0, 255, 0]` for green screen
+/// `tolerance`: chroma distance (0..1) within which pixels become fully transparent
+/// `softness`: width of the band past `tolerance` where alpha ramps back up
+///
+/// Distance is measured on the BT.709 Cb/Cr plane, ignoring luma, so shadows
+/// and highlights on the backdrop key out evenly. Existing alpha is scaled, never
+/// raised, and RGB is left as-is so edges can be cleaned up afterwards with
+/// [`decontaminate_edges`]. Returns early unless `key_rgb` is 3 bytes.
+#[wasm_bindgen]
+pub fn apply_chroma_key(image_data: &mut [u8], key_rgb: &[u8], tolerance: f32, softness: f32) {
+    if !image_data.len().is_multiple_of(4) || key_rgb.len() != 3 {
+        return;
+    }
+
+    let (key_cb, key_cr) = chroma(key_rgb[0], key_rgb[1], key_rgb[2]);
+    let tolerance = tolerance.max(0.0);
+    let softness = softness.max(0.0);
+
+    for_each_pixel(image_data, |px| {
+        let (cb, cr) = chroma(px[0], px[1], px[2]);
+        let d = ((cb - key_cb).powi(2) + (cr - key_cr).powi(2)).sqrt();
+        let keep = if d <= tolerance {
+            0.0
+        } else if d < tolerance + softness {
+            (d - tolerance) / softness
+        } else {
+            return;
+        };
+        px[3] = (px[3] as f32 * keep).round() as u8;
+    });
+}
+
+/// Multiply RGB by alpha, converting straight alpha to premultiplied.
+///
+/// `image_data`: RGBA pixels, modified in place
+#[wasm_bindgen]
+pub fn premultiply_alpha(image_data: &mut [u8]) {
+    if !image_data.len().is_multiple_of(4) {
+        return;
+    }
+
+    for_each_pixel(image_data, |px| {
+        let a = px[3] as u32;
+        for v in &mut px[..3] {
+            *v = ((*v as u32 * a + 127) / 255) as u8;
+        }
+    });
+}
+
+/// Divide RGB by alpha, converting premultiplied alpha back to straight.
+///
+/// `image_data`: RGBA pixels, modified in place
+///
+/// Fully transparent pixels keep their RGB as-is.
+#[wasm_bindgen]
+pub fn unpremultiply_alpha(image_data: &mut [u8]) {
+    if !image_data.len().is_multiple_of(4) {
+        return;
+    }
+
+    for_each_pixel(image_data, |px| {
+        let a = px[3] as u32;
+        if a == 0 {
+            return;
+        }
+        for v in &mut px[..3] {
+            *v = ((*v as u32 * 255 + a / 2) / a).min(255) as u8;
+        }
+    });
+}
+
+/// SplitMix64 finalizer: a stateless hash, so noise for a pixel depends only on
+/// the seed and its index and can be computed in any order.
+#[inline]
+fn splitmix64(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Uniform value in -1..1 derived from `seed` and `n`.
+#[inline]
+fn signed_noise(seed: u64, n: u64) -> f32 {
+    let h = splitmix64(seed ^ splitmix64(n));
+    (h >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+}
+
+/// Add reproducible film grain.
+///
+/// `image_data`: RGBA pixels, modified in place
+/// `amount`: maximum offset as a fraction of full range (0..1)
+/// `monochrome`: use the same offset for R, G and B instead of per-channel noise
+/// `seed`: same seed and buffer size always give the same grain
+///
+/// Noise is uniform in `-amount..amount` and results are clamped. Reusing a seed
+/// across video frames keeps the grain from flickering. Alpha is untouched.
+#[wasm_bindgen]
+pub fn apply_noise(image_data: &mut [u8], amount: f32, monochrome: bool, seed: u64) {
+    if !image_data.len().is_multiple_of(4) || amount == 0.0 {
+        return;
+    }
+
+    let scale = amount * 255.0;
+    for_each_pixel_indexed(image_data, |i, px| {
+        for (c, v) in px[..3].iter_mut().enumerate() {
+            let n = if monochrome {
+                i as u64
+            } else {
+                i as u64 * 3 + c as u64
+            };
+            let offset = signed_noise(seed, n) * scale;
+            *v = (*v as f32 + offset).round().clamp(0.0, 255.0) as u8;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Opaque red keyed against green, with a soft matte several pixels wide.
+    fn soft_matte(alphas: &[u8]) -> Vec<u8> {
+        alphas
+            .iter()
+            .flat_map(|&a| {
+                let t = a as f32 / 255.0;
+                let g = ((1.0 - t) * 255.0).round() as u8;
+                [(t * 255.0).round() as u8, g, 0, a]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn decontaminate_edges_cleans_wide_soft_mattes() {
+        let alphas = [255, 255, 204, 153, 102, 51, 0, 0];
+        let mut data = soft_matte(&alphas);
+        let keyed = data.clone();
+        decontaminate_edges(&mut data, alphas.len() as u32, 1);
+
+        for (x, (px, before)) in data.chunks_exact(4).zip(keyed.chunks_exact(4)).enumerate() {
+            if before[3] == 0 {
+                assert_eq!(px, before, "keyed-out pixel {x} changed");
+            } else {
+                assert_eq!(px, [255, 0, 0, before[3]], "green left in pixel {x}");
+            }
+        }
+    }
+
+    #[test]
+    fn decontaminate_edges_skips_pixels_without_both_references() {
+        let mut gray = [100, 100, 100, 128].repeat(9);
+        decontaminate_edges(&mut gray, 3, 3);
+        assert_eq!(gray, [100, 100, 100, 128].repeat(9));
+
+        // Opaque neighbors but nothing transparent to estimate the background.
+        let mut data = soft_matte(&[255, 204, 153, 102]);
+        let before = data.clone();
+        decontaminate_edges(&mut data, 4, 1);
+        assert_eq!(data, before);
+    }
+}
@@ -0,0 +1,42 @@
+//! Input checks and errors shared by the animation encoders.
+
+use std::fmt::Display;
+
+use wasm_bindgen::prelude::*;
+
+/// The first `frame_count` frames of `rgba_data` and the size of one frame, or
+/// an error if it's too short.
+///
+/// Sizes are overflow-checked: `usize` is 32 bits on WASM, where a wrapped
+/// product would let undersized input through.
+pub(crate) fn checked_frames(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    frame_count: u32,
+) -> Result<(&[u8], usize), JsValue> {
+    let too_large = || {
+        JsValue::from_str(&format!(
+            "{frame_count} frames of {width}x{height} are too large"
+        ))
+    };
+    let frame_size = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(4))
+        .ok_or_else(too_large)?;
+    let needed = frame_size
+        .checked_mul(frame_count as usize)
+        .ok_or_else(too_large)?;
+    let frames = rgba_data.get(..needed).ok_or_else(|| {
+        JsValue::from_str(&format!(
+            "expected {needed} bytes for {frame_count} frames, got {}",
+            rgba_data.len()
+        ))
+    })?;
+    Ok((frames, frame_size))
+}
+
+/// Turn an encoder error into a JS exception message.
+pub(crate) fn encode_error(context: &str, err: impl Display) -> JsValue {
+    JsValue::from_str(&format!("{context}: {err}"))
+}
@@ -1,14 +1,39 @@
-use gif::{Encoder, Frame, Repeat};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::io::Write;
+
+use color_quant::NeuQuant;
+use gif::{ColorOutput, DecodeOptions, DisposalMethod, Encoder, Frame, Repeat};
+use js_sys::Function;
 use wasm_bindgen::prelude::*;
 
+use crate::frames::{checked_frames, encode_error};
+use crate::parallel::{batch_size, map_range};
+use crate::quantize;
+use crate::transform::crop;
+
 /// Encode RGBA frames into a GIF.
 ///
 /// `rgba_data`: all frames concatenated (width * height * 4 bytes per frame)
 /// `width`, `height`: frame dimensions
 /// `frame_count`: number of frames
 /// `delay_cs`: delay between frames in centiseconds (100 = 1 second)
-/// `max_colors`: max palette colors (2-256)
+/// `max_colors`: max palette colors per frame, clamped to 2-256
 /// `speed`: quantization speed (1=best quality, 30=fastest)
+/// `loop_count`: 0 = loop forever, N = play N times in total (1 = play once)
+///
+/// Frames that already fit in `max_colors` keep their exact colors; others are
+/// reduced with NeuQuant to exactly `max_colors` entries, so lower values give
+/// smaller palettes and smaller files.
+///
+/// Fully transparent pixels get a palette index of their own instead of being
+/// flattened onto black. When any frame has them, every frame is disposed to
+/// the background so earlier frames don't show through the holes.
+///
+/// Returns an error if `rgba_data` holds fewer than `frame_count` frames, or one
+/// naming the failing frame if encoding fails. Errors surface as thrown
+/// exceptions in JS.
+#[allow(clippy::too_many_arguments)] // wasm-bindgen exports a flat ABI, so this intentionally stays explicit.
 #[wasm_bindgen]
 pub fn encode_gif_frames(
     rgba_data: &[u8],
@@ -16,26 +41,46 @@ pub fn encode_gif_frames(
     height: u16,
     frame_count: u32,
     delay_cs: u16,
-    _max_colors: u16,
+    max_colors: u16,
     speed: i32,
-) -> Vec<u8> {
+    loop_count: u16,
+) -> Result<Vec<u8>, JsValue> {
     encode_gif_frames_ex(
         rgba_data,
         width,
         height,
         frame_count,
         delay_cs,
-        _max_colors,
+        max_colors,
         speed,
-        0,
+        loop_count,
         &[],
+        false,
+        false,
+        false,
+        false,
+        None,
     )
 }
 
-/// Extended GIF encoder with loop count and per-frame delays.
+/// Extended GIF encoder with per-frame delays and deterministic quantization.
 ///
-/// `loop_count`: 0 = infinite, N = play N times, 0xFFFF = no loop extension
+/// `loop_count`: same as [`encode_gif_frames`]
 /// `frame_delays_cs`: optional per-frame delays (centiseconds). If empty, uses `delay_cs` for all.
+/// `deterministic`: use the median-cut quantizer limited to `max_colors`, so identical
+/// input always produces byte-identical output (useful for golden-file tests)
+/// `global_palette`: build one median-cut palette of `max_colors` from a sample of
+/// every frame and write it as the global color table. Frames then share colors,
+/// which stops gradients shimmering and drops the per-frame color tables. Output
+/// is deterministic and `speed` is unused.
+/// `delta_frames`: after the first frame, write only the rectangle that changed
+/// since the previous frame and keep the rest of the canvas. Mostly static
+/// footage such as screen recordings shrinks dramatically.
+/// `dither`: map pixels to each palette with Floyd-Steinberg error diffusion.
+/// Smooths gradients in photographic content; leave it off for flat graphics,
+/// where it only adds speckle. Frames that fit in the palette are unaffected.
+/// `on_progress`: called as `on_progress(frame_index, frame_count)` after each
+/// frame is written. Exceptions it throws are ignored so they can't abort encoding.
 #[allow(clippy::too_many_arguments)] // wasm-bindgen exports a flat ABI, so this intentionally stays explicit.
 #[wasm_bindgen]
 pub fn encode_gif_frames_ex(
@@ -44,44 +89,874 @@ pub fn encode_gif_frames_ex(
     height: u16,
     frame_count: u32,
     delay_cs: u16,
-    _max_colors: u16,
+    max_colors: u16,
     speed: i32,
     loop_count: u16,
     frame_delays_cs: &[u16],
-) -> Vec<u8> {
-    let frame_size = width as usize * height as usize * 4;
+    deterministic: bool,
+    global_palette: bool,
+    delta_frames: bool,
+    dither: bool,
+    on_progress: Option<Function>,
+) -> Result<Vec<u8>, JsValue> {
+    let (frames, frame_size) = checked_frames(rgba_data, width as u32, height as u32, frame_count)?;
     let mut output = Vec::new();
 
+    let shared = global_palette.then(|| shared_palette(frames, max_colors));
+    let global_colors = shared.as_ref().map_or(&[][..], |(palette, _)| palette);
+    let has_transparency = frames.chunks_exact(4).any(|px| px[3] == 0);
+    let plan = delta_frames.then(|| {
+        delta_plan(
+            frames,
+            width as usize,
+            height as usize,
+            frame_count as usize,
+        )
+    });
+
     {
-        let mut encoder = Encoder::new(&mut output, width, height, &[]).unwrap();
+        let mut encoder = Encoder::new(&mut output, width, height, global_colors)
+            .map_err(|e| encode_error("failed to write GIF header", e))?;
 
-        let repeat = if loop_count == 0 {
-            Repeat::Infinite
-        } else {
-            Repeat::Finite(loop_count)
-        };
-        encoder.set_repeat(repeat).unwrap();
+        write_loop_count(&mut encoder, loop_count)?;
 
         let speed = speed.clamp(1, 30);
-
-        for i in 0..frame_count as usize {
+        let build_frame = |i: usize| {
             let start = i * frame_size;
             let end = start + frame_size;
 
-            if end > rgba_data.len() {
-                break;
-            }
+            let ([left, top, right, bottom], dispose) = match &plan {
+                Some(plan) => plan[i],
+                None => ([0, 0, width as usize, height as usize], None),
+            };
+            let (w, h) = ((right - left) as u16, (bottom - top) as u16);
+            let pixels = if plan.is_some() {
+                Cow::Owned(crop(
+                    &rgba_data[start..end],
+                    width as u32,
+                    height as u32,
+                    left as u32,
+                    top as u32,
+                    w as u32,
+                    h as u32,
+                ))
+            } else {
+                Cow::Borrowed(&rgba_data[start..end])
+            };
 
-            let mut frame_data = rgba_data[start..end].to_vec();
-            let mut frame = Frame::from_rgba_speed(width, height, &mut frame_data, speed);
+            let mut frame = if let Some((palette, transparent)) = &shared {
+                let indices = quantize::map_to_palette(&pixels, palette, *transparent);
+                Frame::from_indexed_pixels(w, h, indices, *transparent)
+            } else if deterministic {
+                quantize_frame_deterministic(w, h, &pixels, max_colors)
+            } else {
+                quantize_frame_neuquant(w, h, &pixels, max_colors, speed)
+            };
+            if dither {
+                let palette = frame.palette.as_deref().unwrap_or(global_colors);
+                let indices =
+                    quantize::dither_to_palette(&pixels, w as usize, palette, frame.transparent);
+                frame.buffer = Cow::Owned(indices);
+            }
+            (frame.left, frame.top) = (left as u16, top as u16);
+            if let Some(dispose) = dispose {
+                frame.dispose = dispose;
+            } else if has_transparency {
+                // Clear before the next frame so its holes don't show this one.
+                frame.dispose = DisposalMethod::Background;
+            }
             frame.delay = if i < frame_delays_cs.len() {
                 frame_delays_cs[i]
             } else {
                 delay_cs
             };
-            encoder.write_frame(&frame).unwrap();
+            frame
+        };
+
+        // Quantize a batch of frames at once (in parallel with the `parallel`
+        // feature), then write them in order.
+        let batch = batch_size();
+        for first in (0..frame_count as usize).step_by(batch) {
+            let last = (first + batch).min(frame_count as usize);
+            for (i, frame) in (first..last).zip(map_range(first..last, build_frame)) {
+                encoder
+                    .write_frame(&frame)
+                    .map_err(|e| encode_error(&format!("failed to write frame {i}"), e))?;
+
+                if let Some(callback) = &on_progress {
+                    let _ = callback.call2(
+                        &JsValue::NULL,
+                        &JsValue::from(i as u32),
+                        &JsValue::from(frame_count),
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+/// Rectangle (`[left, top, right, bottom]`) and disposal for every frame of a
+/// delta-encoded animation.
+///
+/// Each frame after the first covers only the pixels that differ from the
+/// previous one and leaves the canvas in place for the next. Pixels that turn
+/// transparent can't be drawn over the old content, so the previous frame's
+/// rectangle grows to cover them and is disposed to the background instead;
+/// the current frame then redraws that whole rectangle.
+fn delta_plan(
+    frames: &[u8],
+    width: usize,
+    height: usize,
+    frame_count: usize,
+) -> Vec<([usize; 4], Option<DisposalMethod>)> {
+    let frame_size = width * height * 4;
+    let full = [0, 0, width, height];
+    if frame_size == 0 {
+        // Nothing to diff; still plan every frame so callers can index by frame.
+        return vec![(full, None); frame_count];
+    }
+
+    let mut plan = Vec::with_capacity(frame_count);
+    let mut previous: Option<&[u8]> = None;
+    for frame in frames.chunks_exact(frame_size) {
+        let Some(prev) = previous.replace(frame) else {
+            plan.push((full, Some(DisposalMethod::Keep)));
+            continue;
+        };
+
+        let mut changed: Option<[usize; 4]> = None;
+        let mut cleared: Option<[usize; 4]> = None;
+        for (i, (a, b)) in prev.chunks_exact(4).zip(frame.chunks_exact(4)).enumerate() {
+            if a == b || (a[3] == 0 && b[3] == 0) {
+                continue;
+            }
+            let px = [i % width, i / width, i % width + 1, i / width + 1];
+            changed = Some(changed.map_or(px, |r| union_rect(r, px)));
+            if b[3] == 0 {
+                cleared = Some(cleared.map_or(px, |r| union_rect(r, px)));
+            }
+        }
+
+        let mut rect = changed.unwrap_or([0, 0, 1, 1]);
+        if let Some(cleared) = cleared {
+            let last = plan.last_mut().expect("previous frame is planned");
+            *last = (
+                union_rect(last.0, cleared),
+                Some(DisposalMethod::Background),
+            );
+            rect = union_rect(rect, last.0);
+        }
+        plan.push((rect, Some(DisposalMethod::Keep)));
+    }
+    plan
+}
+
+/// Smallest rectangle containing both `a` and `b`.
+fn union_rect(a: [usize; 4], b: [usize; 4]) -> [usize; 4] {
+    [
+        a[0].min(b[0]),
+        a[1].min(b[1]),
+        a[2].max(b[2]),
+        a[3].max(b[3]),
+    ]
+}
+
+/// Encode RGBA frames into a looping GIF with a delay per frame.
+///
+/// `delays_cs`: one delay per frame in centiseconds, e.g. to hold the last frame
+///
+/// Other parameters match [`encode_gif_frames`]. Returns an error if
+/// `delays_cs` doesn't have exactly `frame_count` entries.
+#[wasm_bindgen]
+pub fn encode_gif_frames_variable(
+    rgba_data: &[u8],
+    width: u16,
+    height: u16,
+    frame_count: u32,
+    delays_cs: &[u16],
+    max_colors: u16,
+    speed: i32,
+) -> Result<Vec<u8>, JsValue> {
+    if delays_cs.len() != frame_count as usize {
+        return Err(JsValue::from_str(&format!(
+            "expected {frame_count} frame delays, got {}",
+            delays_cs.len()
+        )));
+    }
+
+    encode_gif_frames_ex(
+        rgba_data,
+        width,
+        height,
+        frame_count,
+        0,
+        max_colors,
+        speed,
+        0,
+        delays_cs,
+        false,
+        false,
+        false,
+        false,
+        None,
+    )
+}
+
+/// Encode RGBA frames into a GIF using a fixed, caller-provided palette.
+///
+/// `palette`: RGB triplets written as the global color table (1-256 entries)
+/// `dither`: spread the mapping error with Floyd-Steinberg diffusion, which
+/// hides banding when the palette is far from the source colors
+///
+/// Every pixel maps to a palette entry, so output colors never drift from the
+/// palette. Other parameters match [`encode_gif_frames`]. Returns an
+/// error if the palette is empty, longer than 768 bytes or not a multiple of 3,
+/// if `rgba_data` holds fewer than `frame_count` frames, or if encoding fails.
+#[wasm_bindgen]
+pub fn encode_gif_frames_with_palette(
+    rgba_data: &[u8],
+    width: u16,
+    height: u16,
+    frame_count: u32,
+    delay_cs: u16,
+    palette: &[u8],
+    dither: bool,
+) -> Result<Vec<u8>, JsValue> {
+    if palette.is_empty() || palette.len() > 256 * 3 || !palette.len().is_multiple_of(3) {
+        return Err(JsValue::from_str("palette must hold 1-256 RGB triplets"));
+    }
+
+    let (_, frame_size) = checked_frames(rgba_data, width as u32, height as u32, frame_count)?;
+    let mut output = Vec::new();
+
+    {
+        let mut encoder = Encoder::new(&mut output, width, height, palette)
+            .map_err(|e| encode_error("failed to write GIF header", e))?;
+        write_loop_count(&mut encoder, 0)?;
+
+        for i in 0..frame_count as usize {
+            let start = i * frame_size;
+            let end = start + frame_size;
+
+            let indices = if dither {
+                quantize::dither_to_palette(&rgba_data[start..end], width as usize, palette, None)
+            } else {
+                quantize::map_to_palette(&rgba_data[start..end], palette, None)
+            };
+            let mut frame = Frame::from_indexed_pixels(width, height, indices, None);
+            frame.delay = delay_cs;
+            encoder
+                .write_frame(&frame)
+                .map_err(|e| encode_error(&format!("failed to write frame {i}"), e))?;
         }
     }
 
-    output
+    Ok(output)
+}
+
+/// Incremental GIF encoder that takes frames one at a time.
+///
+/// Only the encoded bytes and one pending frame are kept, so long videos can be
+/// fed straight from a decode loop without holding every RGBA frame in memory.
+/// Frames are quantized like [`encode_gif_frames`].
+#[wasm_bindgen]
+pub struct GifEncoder {
+    encoder: Encoder<Vec<u8>>,
+    width: u16,
+    height: u16,
+    max_colors: u16,
+    speed: i32,
+    frame_count: u32,
+    /// Last added frame, held back until the next one shows whether it must be
+    /// disposed to the background.
+    pending: Option<Frame<'static>>,
+}
+
+#[wasm_bindgen]
+impl GifEncoder {
+    /// Start a GIF of `width` x `height` frames.
+    ///
+    /// `max_colors`, `speed` and `loop_count` match [`encode_gif_frames`].
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        width: u16,
+        height: u16,
+        max_colors: u16,
+        speed: i32,
+        loop_count: u16,
+    ) -> Result<GifEncoder, JsValue> {
+        let mut encoder = Encoder::new(Vec::new(), width, height, &[])
+            .map_err(|e| encode_error("failed to write GIF header", e))?;
+        write_loop_count(&mut encoder, loop_count)?;
+
+        Ok(Self {
+            encoder,
+            width,
+            height,
+            max_colors,
+            speed: speed.clamp(1, 30),
+            frame_count: 0,
+            pending: None,
+        })
+    }
+
+    /// Quantize and queue one frame.
+    ///
+    /// `rgba`: exactly `width * height * 4` bytes
+    /// `delay_cs`: how long this frame shows, in centiseconds
+    ///
+    /// The previous frame is written now that its disposal is known.
+    pub fn add_frame(&mut self, rgba: &[u8], delay_cs: u16) -> Result<(), JsValue> {
+        let expected = self.width as usize * self.height as usize * 4;
+        if rgba.len() != expected {
+            return Err(JsValue::from_str(&format!(
+                "frame {} has {} bytes, expected {expected}",
+                self.frame_count,
+                rgba.len()
+            )));
+        }
+
+        let mut frame =
+            quantize_frame_neuquant(self.width, self.height, rgba, self.max_colors, self.speed);
+        frame.delay = delay_cs;
+
+        if let Some(mut previous) = self.pending.take() {
+            if frame.transparent.is_some() {
+                // Clear before this frame so its holes don't show the previous one.
+                previous.dispose = DisposalMethod::Background;
+            }
+            self.write_frame(&previous, self.frame_count - 1)?;
+        }
+        self.pending = Some(frame);
+        self.frame_count += 1;
+        Ok(())
+    }
+
+    /// Write the last frame and the trailer, returning the finished GIF.
+    pub fn finish(mut self) -> Result<Vec<u8>, JsValue> {
+        if let Some(last) = self.pending.take() {
+            self.write_frame(&last, self.frame_count - 1)?;
+        }
+        self.encoder
+            .into_inner()
+            .map_err(|e| JsValue::from_str(&format!("failed to write GIF trailer: {e}")))
+    }
+}
+
+impl GifEncoder {
+    fn write_frame(&mut self, frame: &Frame, index: u32) -> Result<(), JsValue> {
+        self.encoder
+            .write_frame(frame)
+            .map_err(|e| encode_error(&format!("failed to write frame {index}"), e))
+    }
+}
+
+/// Write the NETSCAPE looping extension for `loop_count` total plays.
+///
+/// The extension stores extra repetitions after the first play and 0 means
+/// forever, so playing once is expressed by leaving it out.
+fn write_loop_count<W: Write>(encoder: &mut Encoder<W>, loop_count: u16) -> Result<(), JsValue> {
+    let repeat = match loop_count {
+        0 => Repeat::Infinite,
+        1 => return Ok(()),
+        n => Repeat::Finite(n - 1),
+    };
+    encoder
+        .set_repeat(repeat)
+        .map_err(|e| encode_error("failed to write loop count", e))
+}
+
+/// Quantize one RGBA frame with NeuQuant, limited to `max_colors` entries.
+///
+/// Alpha is flattened to 0 or 255 and every fully transparent pixel becomes
+/// transparent black, so they all share one transparent index. Frames with at
+/// most `max_colors` distinct colors go through [`Frame::from_rgba_speed`] and
+/// keep exact colors. Otherwise NeuQuant learns from the opaque pixels only and
+/// the last slot is reserved for transparency, so no opaque color is ever keyed
+/// out.
+fn quantize_frame_neuquant(
+    width: u16,
+    height: u16,
+    rgba: &[u8],
+    max_colors: u16,
+    speed: i32,
+) -> Frame<'static> {
+    let max_colors = max_colors.clamp(2, 256) as usize;
+    let mut pixels = rgba.to_vec();
+    let mut has_transparency = false;
+    for px in pixels.chunks_exact_mut(4) {
+        if px[3] != 0 {
+            px[3] = 0xFF;
+        } else {
+            px.copy_from_slice(&[0, 0, 0, 0]);
+            has_transparency = true;
+        }
+    }
+
+    let mut colors = HashSet::new();
+    let fits = pixels.chunks_exact(4).all(|px| {
+        colors.insert([px[0], px[1], px[2], px[3]]);
+        colors.len() <= max_colors
+    });
+    if fits {
+        return Frame::from_rgba_speed(width, height, &mut pixels, speed);
+    }
+
+    let opaque: Vec<u8> = pixels
+        .chunks_exact(4)
+        .filter(|px| px[3] != 0)
+        .flatten()
+        .copied()
+        .collect();
+    let opaque_colors = if has_transparency {
+        max_colors - 1
+    } else {
+        max_colors
+    };
+    let nq = NeuQuant::new(speed, opaque_colors, &opaque);
+    let transparent = has_transparency.then_some(opaque_colors as u8);
+    let indices: Vec<u8> = pixels
+        .chunks_exact(4)
+        .map(|px| match transparent {
+            Some(index) if px[3] == 0 => index,
+            _ => nq.index_of(px) as u8,
+        })
+        .collect();
+
+    let mut palette = nq.color_map_rgb();
+    if has_transparency {
+        palette.extend_from_slice(&[0, 0, 0]);
+    }
+    Frame::from_palette_pixels(width, height, indices, palette, transparent)
+}
+
+/// Max pixels sampled across all frames when building a shared palette.
+const SHARED_PALETTE_SAMPLE: usize = 1 << 20;
+
+/// Median-cut palette of at most `max_colors` entries built from `sample`.
+///
+/// When `has_transparency` is set, the last slot is reserved for fully
+/// transparent pixels and its index is returned; otherwise the palette holds up
+/// to `max_colors` opaque colors. The palette is never empty.
+fn median_cut_palette(
+    sample: &[u8],
+    has_transparency: bool,
+    max_colors: u16,
+) -> (Vec<u8>, Option<u8>) {
+    let max_colors = max_colors.clamp(2, 256) as usize;
+    let opaque_colors = if has_transparency {
+        max_colors - 1
+    } else {
+        max_colors
+    };
+    let mut palette = quantize::median_cut(sample, opaque_colors);
+    let transparent = has_transparency.then_some((palette.len() / 3) as u8);
+
+    if has_transparency || palette.is_empty() {
+        palette.extend_from_slice(&[0, 0, 0]);
+    }
+    (palette, transparent)
+}
+
+/// One palette for every frame in `frames`, built from an evenly strided sample.
+fn shared_palette(frames: &[u8], max_colors: u16) -> (Vec<u8>, Option<u8>) {
+    let pixel_count = frames.len() / 4;
+    let step = pixel_count.div_ceil(SHARED_PALETTE_SAMPLE).max(1);
+    let sample: Vec<u8> = frames
+        .chunks_exact(4)
+        .step_by(step)
+        .flatten()
+        .copied()
+        .collect();
+    let has_transparency = frames.chunks_exact(4).any(|px| px[3] == 0);
+    median_cut_palette(&sample, has_transparency, max_colors)
+}
+
+/// Quantize one RGBA frame with the reproducible median-cut quantizer.
+///
+/// Fully transparent pixels get a dedicated palette slot, so at most
+/// `max_colors - 1` opaque colors are kept when the frame has any.
+fn quantize_frame_deterministic(
+    width: u16,
+    height: u16,
+    rgba: &[u8],
+    max_colors: u16,
+) -> Frame<'static> {
+    let has_transparency = rgba.chunks_exact(4).any(|px| px[3] == 0);
+    let (palette, transparent) = median_cut_palette(rgba, has_transparency, max_colors);
+    let indices = quantize::map_to_palette(rgba, &palette, transparent);
+    Frame::from_palette_pixels(width, height, indices, palette, transparent)
+}
+
+/// A single RGBA image produced by GIF decoding.
+#[wasm_bindgen]
+pub struct DecodedImage {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl DecodedImage {
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// RGBA pixels, `width * height * 4` bytes.
+    #[wasm_bindgen(getter)]
+    pub fn data(&self) -> Vec<u8> {
+        self.data.clone()
+    }
+}
+
+/// Logical-screen canvas that GIF frames are drawn onto in order.
+struct GifCanvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+    /// Disposal of the last drawn frame, applied before the next one is drawn.
+    pending: Option<(DisposalMethod, [usize; 4], Option<Vec<u8>>)>,
+}
+
+impl GifCanvas {
+    fn new(width: u16, height: u16) -> Self {
+        let (width, height) = (width as usize, height as usize);
+        Self {
+            width,
+            height,
+            pixels: vec![0; width * height * 4],
+            pending: None,
+        }
+    }
+
+    /// Draw an RGBA-decoded frame, honoring its offset and the previous frame's disposal.
+    fn draw(&mut self, frame: &Frame) {
+        if let Some((dispose, [left, top, right, bottom], saved)) = self.pending.take() {
+            match dispose {
+                DisposalMethod::Background => {
+                    for y in top..bottom {
+                        self.pixels[(y * self.width + left) * 4..(y * self.width + right) * 4]
+                            .fill(0);
+                    }
+                }
+                DisposalMethod::Previous => {
+                    if let Some(saved) = saved {
+                        self.pixels = saved;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let saved = (frame.dispose == DisposalMethod::Previous).then(|| self.pixels.clone());
+
+        let frame_w = frame.width as usize;
+        let left = (frame.left as usize).min(self.width);
+        let top = (frame.top as usize).min(self.height);
+        let right = (left + frame_w).min(self.width);
+        let bottom = (top + frame.height as usize).min(self.height);
+
+        for y in top..bottom {
+            let src_row = (y - top) * frame_w * 4;
+            for x in left..right {
+                let src = src_row + (x - left) * 4;
+                let Some(px) = frame.buffer.get(src..src + 4) else {
+                    continue;
+                };
+                if px[3] == 0 {
+                    continue;
+                }
+                let dst = (y * self.width + x) * 4;
+                self.pixels[dst..dst + 4].copy_from_slice(px);
+            }
+        }
+
+        self.pending = Some((frame.dispose, [left, top, right, bottom], saved));
+    }
+}
+
+/// Composite a GIF up to one frame and return it as a still image.
+///
+/// `data`: GIF file bytes
+/// `frame_index`: frame to render; defaults to the last frame
+///
+/// Earlier frames are drawn first so offsets and disposal methods are honored.
+/// Returns `None` when the file can't be decoded or the frame doesn't exist.
+#[wasm_bindgen]
+pub fn gif_poster(data: &[u8], frame_index: Option<u32>) -> Option<DecodedImage> {
+    let mut options = DecodeOptions::new();
+    options.set_color_output(ColorOutput::RGBA);
+    let mut decoder = options.read_info(data).ok()?;
+
+    let mut canvas = GifCanvas::new(decoder.width(), decoder.height());
+    let mut drawn = 0u32;
+    while let Some(frame) = decoder.read_next_frame().ok()? {
+        canvas.draw(frame);
+        drawn += 1;
+        if frame_index == Some(drawn - 1) {
+            break;
+        }
+    }
+
+    if drawn == 0 || frame_index.is_some_and(|i| i >= drawn) {
+        return None;
+    }
+
+    Some(DecodedImage {
+        width: canvas.width as u32,
+        height: canvas.height as u32,
+        data: canvas.pixels,
+    })
+}
+
+/// Every frame of an animated GIF, composited to full RGBA canvases.
+#[wasm_bindgen]
+pub struct DecodedGif {
+    width: u32,
+    height: u32,
+    delays_cs: Vec<u16>,
+    data: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl DecodedGif {
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn frame_count(&self) -> u32 {
+        self.delays_cs.len() as u32
+    }
+
+    /// Delay of each frame in centiseconds.
+    #[wasm_bindgen(getter)]
+    pub fn delays_cs(&self) -> Vec<u16> {
+        self.delays_cs.clone()
+    }
+
+    /// All frames concatenated, `width * height * 4` bytes each, in the layout
+    /// [`encode_gif_frames`] takes.
+    #[wasm_bindgen(getter)]
+    pub fn data(&self) -> Vec<u8> {
+        self.data.clone()
+    }
+}
+
+/// Decode a GIF into full RGBA frames, the inverse of [`encode_gif_frames`].
+///
+/// `data`: GIF file bytes
+///
+/// Each frame is composited onto the canvas left by the previous ones, honoring
+/// offsets and disposal methods, so every output frame is what a viewer shows.
+/// Returns an error if the file can't be decoded.
+#[wasm_bindgen]
+pub fn decode_gif(data: &[u8]) -> Result<DecodedGif, JsValue> {
+    let mut options = DecodeOptions::new();
+    options.set_color_output(ColorOutput::RGBA);
+    let mut decoder = options
+        .read_info(data)
+        .map_err(|e| JsValue::from_str(&format!("failed to read GIF header: {e}")))?;
+
+    let mut canvas = GifCanvas::new(decoder.width(), decoder.height());
+    let mut delays_cs = Vec::new();
+    let mut frames = Vec::new();
+    loop {
+        let frame = decoder.read_next_frame().map_err(|e| {
+            JsValue::from_str(&format!("failed to read frame {}: {e}", delays_cs.len()))
+        })?;
+        let Some(frame) = frame else {
+            break;
+        };
+        canvas.draw(frame);
+        delays_cs.push(frame.delay);
+        frames.extend_from_slice(&canvas.pixels);
+    }
+
+    Ok(DecodedGif {
+        width: canvas.width as u32,
+        height: canvas.height as u32,
+        delays_cs,
+        data: frames,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const W: u16 = 6;
+    const H: u16 = 5;
+
+    /// Three frames with few colors so every quantizer keeps them exact: a
+    /// gradient, the same with a transparent hole punched in it, then a small
+    /// opaque change inside the hole.
+    fn sample_frames() -> Vec<u8> {
+        let colors = [[255, 0, 0], [0, 255, 0], [0, 0, 255], [255, 255, 0]];
+        let base: Vec<u8> = (0..W as usize * H as usize)
+            .flat_map(|i| {
+                let [r, g, b] = colors[(i % W as usize) / 2 + i / (W as usize * 3)];
+                [r, g, b, 255]
+            })
+            .collect();
+
+        let mut holed = base.clone();
+        for (x, y) in [(2, 1), (3, 1), (2, 2), (3, 2)] {
+            let i = (y * W as usize + x) * 4;
+            holed[i..i + 4].fill(0);
+        }
+
+        let mut patched = holed.clone();
+        let i = (W as usize + 2) * 4;
+        patched[i..i + 4].copy_from_slice(&[255, 255, 255, 255]);
+
+        [base, holed, patched].concat()
+    }
+
+    fn encode(frames: &[u8], options: [bool; 4]) -> Vec<u8> {
+        let [deterministic, global_palette, delta_frames, dither] = options;
+        encode_gif_frames_ex(
+            frames,
+            W,
+            H,
+            3,
+            7,
+            16,
+            10,
+            0,
+            &[4, 5, 6],
+            deterministic,
+            global_palette,
+            delta_frames,
+            dither,
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn round_trips_every_option_combination() {
+        let frames = sample_frames();
+        for bits in 0..16u8 {
+            let options = std::array::from_fn(|b| bits & (1 << b) != 0);
+            let decoded = decode_gif(&encode(&frames, options)).unwrap();
+
+            assert_eq!((decoded.width(), decoded.height()), (W as u32, H as u32));
+            assert_eq!(decoded.delays_cs(), vec![4, 5, 6], "{options:?}");
+            assert_eq!(decoded.data(), frames, "{options:?}");
+        }
+    }
+
+    #[test]
+    fn canvas_restores_previous_and_clears_background() {
+        let frame = |left, top, rgba: &[u8], dispose| Frame {
+            width: 1,
+            height: 1,
+            left,
+            top,
+            buffer: Cow::Owned(rgba.to_vec()),
+            dispose,
+            ..Frame::default()
+        };
+        let red = [255, 0, 0, 255];
+        let blue = [0, 0, 255, 255];
+
+        let mut canvas = GifCanvas::new(2, 1);
+        canvas.draw(&frame(0, 0, &red, DisposalMethod::Keep));
+        canvas.draw(&frame(1, 0, &blue, DisposalMethod::Previous));
+        assert_eq!(canvas.pixels, [red, blue].concat());
+
+        // Frame 1 is undone before frame 2 draws; transparent pixels show through.
+        canvas.draw(&frame(1, 0, &[9, 9, 9, 0], DisposalMethod::Background));
+        assert_eq!(canvas.pixels, [red, [0; 4]].concat());
+
+        canvas.draw(&frame(1, 0, &blue, DisposalMethod::Keep));
+        assert_eq!(canvas.pixels, [red, blue].concat());
+    }
+
+    #[test]
+    fn delta_plan_shrinks_to_changes_and_clears_holes() {
+        let frames = sample_frames();
+        let plan = delta_plan(&frames, W as usize, H as usize, 3);
+
+        // The hole can't be drawn over frame 0, so it is cleared to the
+        // background instead and frame 1 redraws the whole frame.
+        let full = [0, 0, W as usize, H as usize];
+        assert_eq!(plan[0], (full, Some(DisposalMethod::Background)));
+        assert_eq!(plan[1], (full, Some(DisposalMethod::Keep)));
+        assert_eq!(plan[2], ([2, 1, 3, 2], Some(DisposalMethod::Keep)));
+    }
+
+    #[test]
+    fn delta_plan_covers_zero_size_frames() {
+        assert_eq!(delta_plan(&[], 0, 4, 3), vec![([0, 0, 0, 4], None); 3]);
+        assert!(
+            encode_gif_frames_ex(
+                &[],
+                0,
+                4,
+                3,
+                5,
+                16,
+                10,
+                0,
+                &[],
+                false,
+                false,
+                true,
+                false,
+                None
+            )
+            .is_ok()
+        );
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_batches_match_serial_output() {
+        let pool = |threads| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+        };
+        let frames = sample_frames().repeat(3);
+        let encode_all = || {
+            [false, true].map(|deterministic| {
+                encode_gif_frames_ex(
+                    &frames,
+                    W,
+                    H,
+                    9,
+                    5,
+                    4,
+                    10,
+                    0,
+                    &[],
+                    deterministic,
+                    false,
+                    true,
+                    true,
+                    None,
+                )
+                .unwrap()
+            })
+        };
+
+        assert_eq!(pool(1).install(encode_all), pool(4).install(encode_all));
+    }
 }
@@ -1,4 +1,36 @@
+pub mod analysis;
+pub mod apng;
+pub mod color;
+pub mod convolve;
+pub mod filters;
+mod frames;
 pub mod gif;
+mod parallel;
+mod quantize;
+pub mod resize;
+pub mod transform;
+mod vp8;
+pub mod webp;
 
+pub use analysis::{DiffResult, compute_histogram, image_diff};
+pub use apng::encode_apng_frames;
+pub use color::{pack_rgb, unpack_rgb};
+pub use convolve::{apply_convolution, apply_gaussian_blur, apply_unsharp_mask, pad_image};
+pub use filters::{
+    apply_channel_mixer, apply_chroma_key, apply_curve, apply_duotone, apply_exposure,
+    apply_filters, apply_filters_copy, apply_filters_linear, apply_filters_masked,
+    apply_filters_premultiplied, apply_filters_region, apply_filters_u16, apply_gamma,
+    apply_invert, apply_levels, apply_lut3d, apply_noise, apply_posterize, apply_sepia,
+    apply_solarize, apply_threshold, apply_vibrance, apply_vignette, apply_white_balance,
+    auto_contrast, auto_levels, auto_white_balance, cartoonize, decontaminate_edges,
+    premultiply_alpha, replace_color, simulate_color_blindness, unpremultiply_alpha,
+    white_balance_kelvin,
+};
 pub use gif::encode_gif_frames;
 pub use gif::encode_gif_frames_ex;
+pub use gif::encode_gif_frames_variable;
+pub use gif::encode_gif_frames_with_palette;
+pub use gif::{DecodedGif, DecodedImage, GifEncoder, decode_gif, gif_poster};
+pub use resize::{resize_bilinear, resize_lanczos};
+pub use transform::{crop, flip_horizontal, flip_vertical, rotate_90};
+pub use webp::encode_webp_frames;
@@ -0,0 +1,131 @@
+//! Loop drivers shared by the filters and encoders.
+//!
+//! With the `parallel` feature, work is split across rayon's thread pool;
+//! otherwise (and always on WASM) it runs serially. Each pixel, row or item is
+//! processed independently, so both paths produce identical output.
+
+use std::ops::Range;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Pixels handed to each rayon task when no row geometry is known.
+#[cfg(feature = "parallel")]
+const CHUNK_PIXELS: usize = 16 * 1024;
+
+/// Run `f` on every RGBA pixel, whatever the channel type.
+pub(crate) fn for_each_pixel<T, F>(image_data: &mut [T], f: F)
+where
+    T: Send,
+    F: Fn(&mut [T]) + Sync + Send,
+{
+    #[cfg(feature = "parallel")]
+    image_data
+        .par_chunks_mut(CHUNK_PIXELS * 4)
+        .for_each(|chunk| chunk.chunks_exact_mut(4).for_each(&f));
+
+    #[cfg(not(feature = "parallel"))]
+    image_data.chunks_exact_mut(4).for_each(f);
+}
+
+/// Run `f(i, pixel)` on every RGBA pixel, where `i` is the pixel index.
+pub(crate) fn for_each_pixel_indexed<F>(image_data: &mut [u8], f: F)
+where
+    F: Fn(usize, &mut [u8]) + Sync + Send,
+{
+    #[cfg(feature = "parallel")]
+    image_data
+        .par_chunks_mut(CHUNK_PIXELS * 4)
+        .enumerate()
+        .for_each(|(chunk, pixels)| {
+            for (i, px) in pixels.chunks_exact_mut(4).enumerate() {
+                f(chunk * CHUNK_PIXELS + i, px);
+            }
+        });
+
+    #[cfg(not(feature = "parallel"))]
+    image_data
+        .chunks_exact_mut(4)
+        .enumerate()
+        .for_each(|(i, px)| f(i, px));
+}
+
+/// Run `f(y, row)` on every RGBA row of an image `width` pixels wide, whatever
+/// the channel type.
+pub(crate) fn for_each_row<T, F>(image_data: &mut [T], width: usize, f: F)
+where
+    T: Send,
+    F: Fn(usize, &mut [T]) + Sync + Send,
+{
+    if width == 0 {
+        return;
+    }
+
+    #[cfg(feature = "parallel")]
+    image_data
+        .par_chunks_exact_mut(width * 4)
+        .enumerate()
+        .for_each(|(y, row)| f(y, row));
+
+    #[cfg(not(feature = "parallel"))]
+    image_data
+        .chunks_exact_mut(width * 4)
+        .enumerate()
+        .for_each(|(y, row)| f(y, row));
+}
+
+/// How many independent items are worth handing to [`map_range`] at once.
+pub(crate) fn batch_size() -> usize {
+    #[cfg(feature = "parallel")]
+    return rayon::current_num_threads();
+
+    #[cfg(not(feature = "parallel"))]
+    1
+}
+
+/// Collect `f(i)` for every `i` in `range`, in order.
+pub(crate) fn map_range<T, F>(range: Range<usize>, f: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(usize) -> T + Sync + Send,
+{
+    #[cfg(feature = "parallel")]
+    return range.into_par_iter().map(f).collect();
+
+    #[cfg(not(feature = "parallel"))]
+    range.map(f).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Enough pixels to span several rayon chunks with the `parallel` feature.
+    const PIXELS: usize = 40_000;
+
+    #[test]
+    fn pixel_indices_match_serial_order() {
+        let mut data = vec![0u8; PIXELS * 4];
+        for_each_pixel_indexed(&mut data, |i, px| {
+            px.copy_from_slice(&(i as u32).to_le_bytes())
+        });
+
+        let expected: Vec<u8> = (0..PIXELS as u32).flat_map(u32::to_le_bytes).collect();
+        assert_eq!(data, expected);
+    }
+
+    #[test]
+    fn rows_and_ranges_stay_in_order() {
+        let width = 7;
+        let mut data = vec![0u8; width * 300 * 4];
+        for_each_row(&mut data, width, |y, row| row.fill(y as u8));
+        for (y, row) in data.chunks_exact(width * 4).enumerate() {
+            assert!(row.iter().all(|&v| v == y as u8));
+        }
+
+        assert_eq!(
+            map_range(3..PIXELS, |i| i * 2),
+            (3..PIXELS).map(|i| i * 2).collect::<Vec<_>>()
+        );
+    }
+}
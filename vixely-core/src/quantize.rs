@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::color::{pack_rgb, unpack_rgb};
+
+/// Channel `c` (0 = red, 1 = green, 2 = blue) of a packed `0xRRGGBB` color.
+#[inline]
+fn channel(color: u32, c: usize) -> u8 {
+    let (r, g, b) = unpack_rgb(color);
+    [r, g, b][c]
+}
+
+/// A run of sorted `(color, count)` entries along with its widest channel.
+#[derive(Clone)]
+struct ColorBox {
+    range: Range<usize>,
+    channel: usize,
+    spread: u8,
+}
+
+impl ColorBox {
+    fn new(colors: &[(u32, u32)], range: Range<usize>) -> Self {
+        let slice = &colors[range.clone()];
+        let mut channel_idx = 0;
+        let mut spread = 0;
+        for c in 0..3 {
+            let min = slice.iter().map(|&(v, _)| channel(v, c)).min().unwrap_or(0);
+            let max = slice.iter().map(|&(v, _)| channel(v, c)).max().unwrap_or(0);
+            if max - min > spread {
+                channel_idx = c;
+                spread = max - min;
+            }
+        }
+        Self {
+            range,
+            channel: channel_idx,
+            spread,
+        }
+    }
+}
+
+/// Build a palette of at most `max_colors` entries with median cut.
+///
+/// `rgba`: RGBA pixels; fully transparent pixels are ignored
+///
+/// Colors are deduplicated and sorted before splitting and every tie is broken
+/// on the packed color value, so the same input always yields the same palette.
+/// Returns flat RGB triplets.
+pub(crate) fn median_cut(rgba: &[u8], max_colors: usize) -> Vec<u8> {
+    let mut pixels: Vec<u32> = rgba
+        .chunks_exact(4)
+        .filter(|px| px[3] != 0)
+        .map(|px| pack_rgb(px[0], px[1], px[2]))
+        .collect();
+    pixels.sort_unstable();
+
+    let mut colors: Vec<(u32, u32)> = Vec::new();
+    for color in pixels {
+        match colors.last_mut() {
+            Some((last, count)) if *last == color => *count += 1,
+            _ => colors.push((color, 1)),
+        }
+    }
+
+    let max_colors = max_colors.max(1);
+    if colors.len() <= max_colors {
+        return colors
+            .iter()
+            .flat_map(|&(c, _)| {
+                let (r, g, b) = unpack_rgb(c);
+                [r, g, b]
+            })
+            .collect();
+    }
+
+    let mut boxes = Vec::with_capacity(max_colors);
+    boxes.push(ColorBox::new(&colors, 0..colors.len()));
+    while boxes.len() < max_colors {
+        let mut best: Option<usize> = None;
+        for (i, b) in boxes.iter().enumerate() {
+            if b.range.len() >= 2 && best.is_none_or(|j| b.spread > boxes[j].spread) {
+                best = Some(i);
+            }
+        }
+
+        let Some(i) = best else {
+            break;
+        };
+
+        let ColorBox {
+            range, channel: c, ..
+        } = boxes[i].clone();
+        let slice = &mut colors[range.clone()];
+        slice.sort_unstable_by_key(|&(v, _)| (channel(v, c), v));
+
+        let total: u64 = slice.iter().map(|&(_, n)| n as u64).sum();
+        let mut acc = 0u64;
+        let mut split = 1;
+        for (j, &(_, n)) in slice.iter().enumerate() {
+            acc += n as u64;
+            if acc * 2 >= total {
+                split = j + 1;
+                break;
+            }
+        }
+        let split = split.clamp(1, slice.len() - 1);
+
+        boxes[i] = ColorBox::new(&colors, range.start..range.start + split);
+        boxes.push(ColorBox::new(&colors, range.start + split..range.end));
+    }
+
+    let mut palette = Vec::with_capacity(boxes.len() * 3);
+    for ColorBox { range, .. } in boxes {
+        let mut sums = [0u64; 3];
+        let mut total = 0u64;
+        for &(v, n) in &colors[range] {
+            for (c, sum) in sums.iter_mut().enumerate() {
+                *sum += channel(v, c) as u64 * n as u64;
+            }
+            total += n as u64;
+        }
+        palette.extend(sums.map(|s| ((s + total / 2) / total) as u8));
+    }
+    palette
+}
+
+/// Index of the palette entry closest to `(r, g, b)` in squared RGB distance.
+///
+/// The `skip` entry, usually the transparent slot, is never chosen. Ties
+/// resolve to the lowest index.
+pub(crate) fn nearest_index(palette: &[u8], skip: Option<u8>, r: u8, g: u8, b: u8) -> u8 {
+    let mut best = 0;
+    let mut best_dist = u32::MAX;
+    for (i, entry) in palette.chunks_exact(3).enumerate() {
+        if skip == Some(i as u8) {
+            continue;
+        }
+        let dr = entry[0] as i32 - r as i32;
+        let dg = entry[1] as i32 - g as i32;
+        let db = entry[2] as i32 - b as i32;
+        let dist = (dr * dr + dg * dg + db * db) as u32;
+        if dist < best_dist {
+            best = i;
+            best_dist = dist;
+            if dist == 0 {
+                break;
+            }
+        }
+    }
+    best as u8
+}
+
+/// Map RGBA pixels to indices into an RGB `palette`.
+///
+/// Fully transparent pixels map to `transparent` when one is given, and no
+/// other pixel does.
+pub(crate) fn map_to_palette(rgba: &[u8], palette: &[u8], transparent: Option<u8>) -> Vec<u8> {
+    let mut cache: HashMap<u32, u8> = HashMap::new();
+    rgba.chunks_exact(4)
+        .map(|px| match transparent {
+            Some(index) if px[3] == 0 => index,
+            _ => *cache
+                .entry(pack_rgb(px[0], px[1], px[2]))
+                .or_insert_with(|| nearest_index(palette, transparent, px[0], px[1], px[2])),
+        })
+        .collect()
+}
+
+/// [`map_to_palette`] with Floyd-Steinberg error diffusion.
+///
+/// `width`: pixels per row of `rgba`
+///
+/// The rounding error of every opaque pixel is spread onto its unvisited
+/// neighbors, trading flat banding for fine noise on gradients. Transparent
+/// pixels neither receive nor spread error.
+pub(crate) fn dither_to_palette(
+    rgba: &[u8],
+    width: usize,
+    palette: &[u8],
+    transparent: Option<u8>,
+) -> Vec<u8> {
+    if width == 0 {
+        return Vec::new();
+    }
+
+    let mut out = Vec::with_capacity(rgba.len() / 4);
+    let mut current = vec![[0.0f32; 3]; width + 2];
+    let mut next = vec![[0.0f32; 3]; width + 2];
+
+    for row in rgba.chunks_exact(width * 4) {
+        for (x, px) in row.chunks_exact(4).enumerate() {
+            if let Some(index) = transparent.filter(|_| px[3] == 0) {
+                out.push(index);
+                continue;
+            }
+
+            // Error buffers are offset by one so x - 1 and x + 1 are always in range.
+            let want: [f32; 3] =
+                std::array::from_fn(|c| (px[c] as f32 + current[x + 1][c]).clamp(0.0, 255.0));
+            let [r, g, b] = want.map(|v| v.round() as u8);
+            let index = nearest_index(palette, transparent, r, g, b);
+            out.push(index);
+
+            let entry = &palette[index as usize * 3..index as usize * 3 + 3];
+            for c in 0..3 {
+                let err = want[c] - entry[c] as f32;
+                current[x + 2][c] += err * 7.0 / 16.0;
+                next[x][c] += err * 3.0 / 16.0;
+                next[x + 1][c] += err * 5.0 / 16.0;
+                next[x + 2][c] += err * 1.0 / 16.0;
+            }
+        }
+        std::mem::swap(&mut current, &mut next);
+        next.iter_mut().for_each(|e| *e = [0.0; 3]);
+    }
+    out
+}
@@ -0,0 +1,269 @@
+//! Image resampling.
+
+use std::f32::consts::PI;
+
+use wasm_bindgen::prelude::*;
+
+/// Widest Lanczos kernel accepted; larger values only cost time, since every
+/// output sample visits `2 * a` source taps per unit of scale.
+const MAX_LOBES: u32 = 8;
+
+/// Source taps and normalized weights contributing to one output sample.
+struct Taps {
+    start: usize,
+    weights: Vec<f32>,
+}
+
+fn lanczos_kernel(x: f32, a: f32) -> f32 {
+    if x == 0.0 {
+        1.0
+    } else if x.abs() >= a {
+        0.0
+    } else {
+        let px = PI * x;
+        a * px.sin() * (px / a).sin() / (px * px)
+    }
+}
+
+/// Lanczos taps for every output index along one axis.
+///
+/// When downscaling the kernel is stretched by the scale factor so it also
+/// acts as a low-pass filter. Out-of-range taps clamp to the edge sample.
+fn lanczos_taps(src_len: usize, dst_len: usize, a: f32) -> Vec<Taps> {
+    let scale = src_len as f32 / dst_len as f32;
+    let filter_scale = scale.max(1.0);
+    let support = a * filter_scale;
+
+    (0..dst_len)
+        .map(|i| {
+            let center = (i as f32 + 0.5) * scale - 0.5;
+            let first = (center - support).ceil() as isize;
+            let last = (center + support).floor() as isize;
+
+            let clamp = |j: isize| j.clamp(0, src_len as isize - 1) as usize;
+            let start = clamp(first);
+
+            let mut weights = vec![0.0; clamp(last) - start + 1];
+            for j in first..=last {
+                weights[clamp(j) - start] += lanczos_kernel((j as f32 - center) / filter_scale, a);
+            }
+
+            let sum: f32 = weights.iter().sum();
+            if sum != 0.0 {
+                weights.iter_mut().for_each(|w| *w /= sum);
+            }
+            Taps { start, weights }
+        })
+        .collect()
+}
+
+/// RGBA as f32 with color premultiplied by alpha, so transparent pixels don't
+/// bleed into their neighbors while sampling.
+pub(crate) fn premultiply(src: &[u8]) -> Vec<f32> {
+    src.chunks_exact(4)
+        .flat_map(|px| {
+            let alpha = px[3] as f32 / 255.0;
+            [
+                px[0] as f32 * alpha,
+                px[1] as f32 * alpha,
+                px[2] as f32 * alpha,
+                px[3] as f32,
+            ]
+        })
+        .collect()
+}
+
+/// Byte length of a `width` x `height` RGBA buffer, or `None` if it overflows.
+fn rgba_len(width: usize, height: usize) -> Option<usize> {
+    width.checked_mul(height)?.checked_mul(4)
+}
+
+/// Write a premultiplied sample back as straight RGBA, clamping overshoot.
+fn unpremultiply(acc: [f32; 4], out: &mut [u8]) {
+    let alpha = acc[3].clamp(0.0, 255.0);
+    if alpha > 0.0 {
+        let scale = 255.0 / alpha;
+        for (o, &v) in out[..3].iter_mut().zip(&acc[..3]) {
+            *o = (v * scale).round().clamp(0.0, 255.0) as u8;
+        }
+    }
+    out[3] = alpha.round() as u8;
+}
+
+/// Resize RGBA pixels with separable Lanczos resampling.
+///
+/// `src`: RGBA pixels, `src_w * src_h * 4` bytes
+/// `a`: number of lobes, typically 2 or 3 (clamped to 1-8)
+///
+/// Color is premultiplied by alpha while sampling so transparent pixels don't
+/// bleed into their neighbors, and overshoot from the negative lobes is
+/// clamped. Returns an empty buffer if `src` doesn't match its dimensions, a
+/// dimension is 0, or a working buffer would be too large to address.
+#[wasm_bindgen]
+pub fn resize_lanczos(
+    src: &[u8],
+    src_w: u32,
+    src_h: u32,
+    dst_w: u32,
+    dst_h: u32,
+    a: u32,
+) -> Vec<u8> {
+    let (sw, sh, dw, dh) = (
+        src_w as usize,
+        src_h as usize,
+        dst_w as usize,
+        dst_h as usize,
+    );
+    if sw == 0 || sh == 0 || dw == 0 || dh == 0 || rgba_len(sw, sh) != Some(src.len()) {
+        return Vec::new();
+    }
+    let (Some(horizontal_len), Some(out_len)) = (rgba_len(dw, sh), rgba_len(dw, dh)) else {
+        return Vec::new();
+    };
+    let a = a.clamp(1, MAX_LOBES) as f32;
+
+    let premultiplied = premultiply(src);
+
+    let x_taps = lanczos_taps(sw, dw, a);
+    let mut horizontal = vec![0.0f32; horizontal_len];
+    for y in 0..sh {
+        let src_row = &premultiplied[y * sw * 4..(y + 1) * sw * 4];
+        let dst_row = &mut horizontal[y * dw * 4..(y + 1) * dw * 4];
+        for (x, taps) in x_taps.iter().enumerate() {
+            let mut acc = [0.0f32; 4];
+            for (k, &w) in taps.weights.iter().enumerate() {
+                let s = (taps.start + k) * 4;
+                for c in 0..4 {
+                    acc[c] += src_row[s + c] * w;
+                }
+            }
+            dst_row[x * 4..x * 4 + 4].copy_from_slice(&acc);
+        }
+    }
+
+    let y_taps = lanczos_taps(sh, dh, a);
+    let mut out = vec![0u8; out_len];
+    for (y, taps) in y_taps.iter().enumerate() {
+        for x in 0..dw {
+            let mut acc = [0.0f32; 4];
+            for (k, &w) in taps.weights.iter().enumerate() {
+                let s = ((taps.start + k) * dw + x) * 4;
+                for c in 0..4 {
+                    acc[c] += horizontal[s + c] * w;
+                }
+            }
+
+            let d = (y * dw + x) * 4;
+            unpremultiply(acc, &mut out[d..d + 4]);
+        }
+    }
+
+    out
+}
+
+/// Source index pair and blend weight for one output sample along an axis.
+///
+/// Output pixel centers are mapped onto the source grid and clamped at the
+/// edges.
+fn bilinear_taps(src_len: usize, dst_len: usize) -> Vec<(usize, usize, f32)> {
+    let scale = src_len as f32 / dst_len as f32;
+    (0..dst_len)
+        .map(|i| {
+            let pos = ((i as f32 + 0.5) * scale - 0.5).clamp(0.0, (src_len - 1) as f32);
+            let i0 = pos.floor() as usize;
+            let i1 = (i0 + 1).min(src_len - 1);
+            (i0, i1, pos - i0 as f32)
+        })
+        .collect()
+}
+
+/// Resize RGBA pixels with bilinear interpolation.
+///
+/// `image_data`: RGBA pixels, `src_w * src_h * 4` bytes
+///
+/// Cheaper than [`resize_lanczos`] but softer, and it doesn't low-pass when
+/// shrinking by more than 2x. Like Lanczos, color is premultiplied by alpha
+/// while sampling. Returns an empty buffer if `image_data` doesn't match its
+/// dimensions, a dimension is 0, or the output would be too large to address.
+#[wasm_bindgen]
+pub fn resize_bilinear(
+    image_data: &[u8],
+    src_w: u32,
+    src_h: u32,
+    dst_w: u32,
+    dst_h: u32,
+) -> Vec<u8> {
+    let (sw, sh, dw, dh) = (
+        src_w as usize,
+        src_h as usize,
+        dst_w as usize,
+        dst_h as usize,
+    );
+    if sw == 0 || sh == 0 || dw == 0 || dh == 0 || rgba_len(sw, sh) != Some(image_data.len()) {
+        return Vec::new();
+    }
+    let Some(out_len) = rgba_len(dw, dh) else {
+        return Vec::new();
+    };
+
+    let premultiplied = premultiply(image_data);
+    let x_taps = bilinear_taps(sw, dw);
+    let y_taps = bilinear_taps(sh, dh);
+
+    let mut out = vec![0u8; out_len];
+    for (y, &(y0, y1, fy)) in y_taps.iter().enumerate() {
+        let row0 = &premultiplied[y0 * sw * 4..(y0 + 1) * sw * 4];
+        let row1 = &premultiplied[y1 * sw * 4..(y1 + 1) * sw * 4];
+        for (x, &(x0, x1, fx)) in x_taps.iter().enumerate() {
+            let mut acc = [0.0f32; 4];
+            for (c, v) in acc.iter_mut().enumerate() {
+                let top = row0[x0 * 4 + c] + (row0[x1 * 4 + c] - row0[x0 * 4 + c]) * fx;
+                let bottom = row1[x0 * 4 + c] + (row1[x1 * 4 + c] - row1[x0 * 4 + c]) * fx;
+                *v = top + (bottom - top) * fy;
+            }
+            let d = (y * dw + x) * 4;
+            unpremultiply(acc, &mut out[d..d + 4]);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 9x7 image with varied color and alpha.
+    fn sample() -> Vec<u8> {
+        (0..9 * 7u32)
+            .flat_map(|i| {
+                [
+                    (i * 37 % 256) as u8,
+                    (i * 11) as u8,
+                    (255 - i * 3) as u8,
+                    (i * 90 % 256) as u8,
+                ]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lanczos_clamps_lobe_count() {
+        let src = sample();
+        for (dw, dh) in [(5, 4), (20, 13)] {
+            let resize = |a| resize_lanczos(&src, 9, 7, dw, dh, a);
+            assert_eq!(resize(0), resize(1));
+            assert_eq!(resize(1000), resize(MAX_LOBES));
+            assert_ne!(resize(1), resize(3));
+        }
+    }
+
+    #[test]
+    fn lanczos_rejects_overflowing_output() {
+        assert!(resize_lanczos(&[0; 4], 1, 1, u32::MAX, u32::MAX, 3).is_empty());
+    }
+
+    #[test]
+    fn bilinear_rejects_overflowing_output() {
+        assert!(resize_bilinear(&[0; 4], 1, 1, u32::MAX, u32::MAX).is_empty());
+    }
+}
@@ -0,0 +1,124 @@
+//! Geometric transforms.
+
+use wasm_bindgen::prelude::*;
+
+/// Mirror RGBA pixels left to right, in place.
+///
+/// Does nothing if `image_data` isn't `width * height * 4` bytes.
+#[wasm_bindgen]
+pub fn flip_horizontal(image_data: &mut [u8], width: u32, height: u32) {
+    let w = width as usize;
+    if w == 0 || image_data.len() != w * height as usize * 4 {
+        return;
+    }
+
+    for row in image_data.chunks_exact_mut(w * 4) {
+        for x in 0..w / 2 {
+            let (left, right) = row.split_at_mut((w - 1 - x) * 4);
+            left[x * 4..x * 4 + 4].swap_with_slice(&mut right[..4]);
+        }
+    }
+}
+
+/// Mirror RGBA pixels top to bottom, in place.
+///
+/// Does nothing if `image_data` isn't `width * height * 4` bytes.
+#[wasm_bindgen]
+pub fn flip_vertical(image_data: &mut [u8], width: u32, height: u32) {
+    let (stride, h) = (width as usize * 4, height as usize);
+    if image_data.len() != stride * h {
+        return;
+    }
+
+    for y in 0..h / 2 {
+        let (top, bottom) = image_data.split_at_mut((h - 1 - y) * stride);
+        top[y * stride..(y + 1) * stride].swap_with_slice(&mut bottom[..stride]);
+    }
+}
+
+/// Rotate RGBA pixels by 90 degrees.
+///
+/// `clockwise`: rotate clockwise, otherwise counter-clockwise
+///
+/// The result is `height` pixels wide and `width` pixels tall, so callers must
+/// swap their dimensions. Returns an empty buffer if `image_data` isn't
+/// `width * height * 4` bytes.
+#[wasm_bindgen]
+pub fn rotate_90(image_data: &[u8], width: u32, height: u32, clockwise: bool) -> Vec<u8> {
+    let (w, h) = (width as usize, height as usize);
+    if image_data.len() != w * h * 4 {
+        return Vec::new();
+    }
+
+    let mut out = vec![0u8; image_data.len()];
+    for (i, px) in image_data.chunks_exact(4).enumerate() {
+        let (x, y) = (i % w, i / w);
+        let (dx, dy) = if clockwise {
+            (h - 1 - y, x)
+        } else {
+            (y, w - 1 - x)
+        };
+        let d = (dy * h + dx) * 4;
+        out[d..d + 4].copy_from_slice(px);
+    }
+    out
+}
+
+/// Copy a rectangle out of RGBA pixels.
+///
+/// `x`, `y`: top-left corner of the rectangle
+/// `crop_w`, `crop_h`: rectangle size
+///
+/// The rectangle is clamped to the image bounds, so the result may be smaller
+/// than requested: `min(crop_w, width - x)` by `min(crop_h, height - y)`.
+/// Returns an empty buffer if `image_data` isn't `width * height * 4` bytes or
+/// the rectangle doesn't overlap the image.
+#[wasm_bindgen]
+pub fn crop(
+    image_data: &[u8],
+    width: u32,
+    height: u32,
+    x: u32,
+    y: u32,
+    crop_w: u32,
+    crop_h: u32,
+) -> Vec<u8> {
+    if image_data.len() != width as usize * height as usize * 4 || x >= width || y >= height {
+        return Vec::new();
+    }
+    let cw = crop_w.min(width - x) as usize;
+    let ch = crop_h.min(height - y) as usize;
+
+    let stride = width as usize * 4;
+    let mut out = Vec::with_capacity(cw * ch * 4);
+    for row in image_data.chunks_exact(stride).skip(y as usize).take(ch) {
+        let start = x as usize * 4;
+        out.extend_from_slice(&row[start..start + cw * 4]);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flip_horizontal_ignores_zero_width() {
+        let mut empty: [u8; 0] = [];
+        flip_horizontal(&mut empty, 0, 5);
+    }
+
+    #[test]
+    fn flip_horizontal_mirrors_rows() {
+        let mut image = [
+            1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 6, 6, 6, 6,
+        ];
+        flip_horizontal(&mut image, 3, 2);
+        assert_eq!(
+            image,
+            [
+                3, 3, 3, 3, 2, 2, 2, 2, 1, 1, 1, 1, 6, 6, 6, 6, 5, 5, 5, 5, 4, 4, 4, 4
+            ]
+        );
+    }
+}
@@ -0,0 +1,1217 @@
+//! Lossy VP8 keyframe encoding for the colour part of lossy WebP frames.
+//!
+//! This is a small intra-only encoder. Each macroblock uses whichever of the
+//! four whole-block predictors (DC, vertical, horizontal, TrueMotion) lands
+//! closest to the source, residuals go through the standard VP8 transforms,
+//! and tokens are coded with the default probabilities into one partition.
+
+/// Largest frame side the VP8 header can describe.
+pub(crate) const MAX_DIMENSION: u32 = 16383;
+
+/// The first partition's size has to fit the 19-bit field in the frame tag.
+const MAX_FIRST_PARTITION: usize = (1 << 19) - 1;
+
+/// Largest quantized level the token tree can express.
+const MAX_LEVEL: i32 = 2048;
+
+/// Token probability tables, indexed by block type, band, context and tree node.
+type TokenProbs = [[[[u8; 11]; 3]; 8]; 4];
+
+/// Block types used to pick a token probability table.
+const TYPE_Y_AFTER_Y2: usize = 0;
+const TYPE_Y2: usize = 1;
+const TYPE_CHROMA: usize = 2;
+
+/// Encode the colour channels of an RGBA image as one VP8 keyframe.
+///
+/// `rgba`: width * height * 4 bytes; alpha is ignored
+/// `width`, `height`: image dimensions, 1-16383
+/// `quality`: 0-100, higher keeps more detail
+///
+/// Returns the bitstream that goes inside a `VP8 ` chunk, or an error if the
+/// mode partition outgrows the frame header's size field.
+pub(crate) fn encode_vp8(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    quality: f32,
+) -> Result<Vec<u8>, String> {
+    let qi = ((100.0 - quality.clamp(0.0, 100.0)) * 127.0 / 100.0).round() as usize;
+    let mut encoder = Encoder::new(rgba, width as usize, height as usize, qi);
+    for mby in 0..encoder.mb_height {
+        // Left contexts restart at the beginning of every macroblock row.
+        let mut left = [false; 9];
+        for mbx in 0..encoder.mb_width {
+            encoder.encode_macroblock(mbx, mby, &mut left);
+        }
+    }
+
+    let modes = encoder.header.finish();
+    let tokens = encoder.tokens.finish();
+    if modes.len() > MAX_FIRST_PARTITION {
+        return Err(format!(
+            "VP8 mode partition is {} bytes, the limit is {MAX_FIRST_PARTITION}",
+            modes.len()
+        ));
+    }
+
+    let mut out = Vec::with_capacity(10 + modes.len() + tokens.len());
+    // Key frame, version 0, shown, followed by the first partition's size.
+    let tag = (modes.len() as u32) << 5 | 1 << 4;
+    out.extend_from_slice(&tag.to_le_bytes()[..3]);
+    out.extend_from_slice(&[0x9d, 0x01, 0x2a]);
+    out.extend_from_slice(&(width as u16).to_le_bytes());
+    out.extend_from_slice(&(height as u16).to_le_bytes());
+    out.extend_from_slice(&modes);
+    out.extend_from_slice(&tokens);
+    Ok(out)
+}
+
+/// One 8-bit image plane, padded out to whole macroblocks.
+struct Plane {
+    data: Vec<u8>,
+    stride: usize,
+}
+
+impl Plane {
+    fn new(stride: usize, rows: usize) -> Self {
+        Plane {
+            data: vec![0; stride * rows],
+            stride,
+        }
+    }
+
+    /// Copy the `size` x `size` block at (`x0`, `y0`) into a row-major array.
+    fn block(&self, x0: usize, y0: usize, size: usize) -> [u8; 256] {
+        let mut out = [0; 256];
+        for y in 0..size {
+            let row = (y0 + y) * self.stride + x0;
+            out[y * size..][..size].copy_from_slice(&self.data[row..][..size]);
+        }
+        out
+    }
+
+    /// Write a row-major `size` x `size` block back at (`x0`, `y0`).
+    fn put_block(&mut self, x0: usize, y0: usize, size: usize, block: &[u8; 256]) {
+        for y in 0..size {
+            let row = (y0 + y) * self.stride + x0;
+            self.data[row..][..size].copy_from_slice(&block[y * size..][..size]);
+        }
+    }
+}
+
+/// Convert RGBA to Y, U and V planes with 4:2:0 chroma, repeating the last
+/// column and row into the macroblock padding.
+fn rgba_to_yuv(
+    rgba: &[u8],
+    width: usize,
+    height: usize,
+    mb_width: usize,
+    mb_height: usize,
+) -> [Plane; 3] {
+    let mut y_plane = Plane::new(mb_width * 16, mb_height * 16);
+    let mut u_plane = Plane::new(mb_width * 8, mb_height * 8);
+    let mut v_plane = Plane::new(mb_width * 8, mb_height * 8);
+    let rgb = |x: usize, y: usize| {
+        let i = (y.min(height - 1) * width + x.min(width - 1)) * 4;
+        [rgba[i], rgba[i + 1], rgba[i + 2]].map(i32::from)
+    };
+
+    // Fixed-point BT.601 with studio-range luma, as libwebp does it.
+    for y in 0..mb_height * 16 {
+        for x in 0..mb_width * 16 {
+            let [r, g, b] = rgb(x, y);
+            y_plane.data[y * y_plane.stride + x] =
+                ((16839 * r + 33059 * g + 6420 * b + (1 << 15) + (16 << 16)) >> 16) as u8;
+        }
+    }
+    for y in 0..mb_height * 8 {
+        for x in 0..mb_width * 8 {
+            let mut sum = [0; 3];
+            for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let px = rgb(2 * x + dx, 2 * y + dy);
+                for c in 0..3 {
+                    sum[c] += px[c];
+                }
+            }
+            let [r, g, b] = sum;
+            let chroma = |v: i32| ((v + (1 << 17) + (128 << 18)) >> 18).clamp(0, 255) as u8;
+            u_plane.data[y * u_plane.stride + x] = chroma(-9719 * r - 19081 * g + 28800 * b);
+            v_plane.data[y * v_plane.stride + x] = chroma(28800 * r - 24116 * g - 4684 * b);
+        }
+    }
+    [y_plane, u_plane, v_plane]
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Dc,
+    Vertical,
+    Horizontal,
+    TrueMotion,
+}
+
+const MODES: [Mode; 4] = [Mode::Dc, Mode::Vertical, Mode::Horizontal, Mode::TrueMotion];
+
+impl Mode {
+    /// Tree branches and probabilities for this mode as a key frame luma mode.
+    fn luma_code(self) -> &'static [(bool, u8)] {
+        match self {
+            Mode::Dc => &[(true, 145), (false, 156), (false, 163)],
+            Mode::Vertical => &[(true, 145), (false, 156), (true, 163)],
+            Mode::Horizontal => &[(true, 145), (true, 156), (false, 128)],
+            Mode::TrueMotion => &[(true, 145), (true, 156), (true, 128)],
+        }
+    }
+
+    /// Tree branches and probabilities for this mode as a key frame chroma mode.
+    fn chroma_code(self) -> &'static [(bool, u8)] {
+        match self {
+            Mode::Dc => &[(false, 142)],
+            Mode::Vertical => &[(true, 142), (false, 114)],
+            Mode::Horizontal => &[(true, 142), (true, 114), (false, 183)],
+            Mode::TrueMotion => &[(true, 142), (true, 114), (true, 183)],
+        }
+    }
+}
+
+/// Reconstructed pixels bordering a block, or the fixed stand-ins the decoder
+/// uses at the frame edges.
+struct Edges {
+    above: [u8; 16],
+    left: [u8; 16],
+    corner: u8,
+    has_above: bool,
+    has_left: bool,
+}
+
+impl Edges {
+    fn gather(plane: &Plane, x0: usize, y0: usize, size: usize) -> Self {
+        let has_above = y0 > 0;
+        let has_left = x0 > 0;
+        let mut above = [127; 16];
+        let mut left = [129; 16];
+        if has_above {
+            above[..size].copy_from_slice(&plane.data[(y0 - 1) * plane.stride + x0..][..size]);
+        }
+        if has_left {
+            for (y, l) in left[..size].iter_mut().enumerate() {
+                *l = plane.data[(y0 + y) * plane.stride + x0 - 1];
+            }
+        }
+        let corner = match (has_above, has_left) {
+            (false, _) => 127,
+            (true, false) => 129,
+            (true, true) => plane.data[(y0 - 1) * plane.stride + x0 - 1],
+        };
+        Edges {
+            above,
+            left,
+            corner,
+            has_above,
+            has_left,
+        }
+    }
+
+    /// Predict a `size` x `size` block (16 for luma, 8 for chroma).
+    fn predict(&self, mode: Mode, size: usize) -> [u8; 256] {
+        let dc = self.dc(size);
+        let mut out = [0; 256];
+        for y in 0..size {
+            for x in 0..size {
+                out[y * size + x] = match mode {
+                    Mode::Dc => dc,
+                    Mode::Vertical => self.above[x],
+                    Mode::Horizontal => self.left[y],
+                    Mode::TrueMotion => (i32::from(self.left[y]) + i32::from(self.above[x])
+                        - i32::from(self.corner))
+                    .clamp(0, 255) as u8,
+                };
+            }
+        }
+        out
+    }
+
+    /// Rounded mean of whichever edges exist, or 128 in the top-left block.
+    fn dc(&self, size: usize) -> u8 {
+        let mut sum = 0;
+        let mut count = 0;
+        if self.has_above {
+            sum += self.above[..size]
+                .iter()
+                .map(|&v| u32::from(v))
+                .sum::<u32>();
+            count += size as u32;
+        }
+        if self.has_left {
+            sum += self.left[..size].iter().map(|&v| u32::from(v)).sum::<u32>();
+            count += size as u32;
+        }
+        (sum + count / 2)
+            .checked_div(count)
+            .map_or(128, |dc| dc as u8)
+    }
+}
+
+fn squared_error(a: &[u8], b: &[u8]) -> u64 {
+    a.iter()
+        .zip(b)
+        .map(|(&a, &b)| {
+            let d = i64::from(a) - i64::from(b);
+            (d * d) as u64
+        })
+        .sum()
+}
+
+#[derive(Clone, Copy)]
+struct Quantizer {
+    dc: i32,
+    ac: i32,
+    /// Rounding offsets in 1/256ths of a step; below one half favours zeros.
+    dc_bias: i32,
+    ac_bias: i32,
+}
+
+impl Quantizer {
+    /// Quantize raster-order `coeffs` from zigzag position `first` on.
+    ///
+    /// Returns the levels in zigzag order and the dequantized coefficients in
+    /// raster order, which is what the decoder will reconstruct from.
+    fn quantize(&self, coeffs: &[i32; 16], first: usize) -> ([i32; 16], [i32; 16]) {
+        let mut levels = [0; 16];
+        let mut dequantized = [0; 16];
+        for i in first..16 {
+            let pos = ZIGZAG[i];
+            let (step, bias) = if pos == 0 {
+                (self.dc, self.dc_bias)
+            } else {
+                (self.ac, self.ac_bias)
+            };
+            let level = ((coeffs[pos].abs() * 256 + step * bias) / (step * 256)).min(MAX_LEVEL);
+            let level = if coeffs[pos] < 0 { -level } else { level };
+            levels[i] = level;
+            dequantized[pos] = level * step;
+        }
+        (levels, dequantized)
+    }
+}
+
+/// Step sizes for quantizer index `qi`, derived the way the decoder does.
+fn quantizers(qi: usize) -> [Quantizer; 3] {
+    let dc = i32::from(DC_QUANT[qi]);
+    let ac = i32::from(AC_QUANT[qi]);
+    let y = Quantizer {
+        dc,
+        ac,
+        dc_bias: 96,
+        ac_bias: 110,
+    };
+    let y2 = Quantizer {
+        dc: dc * 2,
+        ac: (ac * 155 / 100).max(8),
+        dc_bias: 96,
+        ac_bias: 108,
+    };
+    let uv = Quantizer {
+        dc: dc.min(132),
+        ac,
+        dc_bias: 110,
+        ac_bias: 115,
+    };
+    [y, y2, uv]
+}
+
+struct Encoder {
+    source: [Plane; 3],
+    recon: [Plane; 3],
+    mb_width: usize,
+    mb_height: usize,
+    y: Quantizer,
+    y2: Quantizer,
+    uv: Quantizer,
+    /// Whether the blocks along each macroblock's bottom edge coded any
+    /// coefficients: Y2, four Y, two U, two V.
+    top: Vec<[bool; 9]>,
+    header: BoolEncoder,
+    tokens: BoolEncoder,
+}
+
+impl Encoder {
+    fn new(rgba: &[u8], width: usize, height: usize, qi: usize) -> Self {
+        let mb_width = width.div_ceil(16);
+        let mb_height = height.div_ceil(16);
+        let source = rgba_to_yuv(rgba, width, height, mb_width, mb_height);
+        let recon = [
+            Plane::new(mb_width * 16, mb_height * 16),
+            Plane::new(mb_width * 8, mb_height * 8),
+            Plane::new(mb_width * 8, mb_height * 8),
+        ];
+        let [y, y2, uv] = quantizers(qi);
+        let mut encoder = Encoder {
+            source,
+            recon,
+            mb_width,
+            mb_height,
+            y,
+            y2,
+            uv,
+            top: vec![[false; 9]; mb_width],
+            header: BoolEncoder::new(),
+            tokens: BoolEncoder::new(),
+        };
+        encoder.write_header(qi);
+        encoder
+    }
+
+    /// Frame header fields of the first partition (RFC 6386 9.2-9.11, 19.2).
+    fn write_header(&mut self, qi: usize) {
+        let h = &mut self.header;
+        h.put_literal(0, 1); // colour space
+        h.put_literal(0, 1); // clamping required
+        h.put_literal(0, 1); // no segmentation
+        h.put_literal(0, 1); // normal loop filter
+        // Deblock harder as the quantizer coarsens.
+        h.put_literal(qi as u32 / 2, 6);
+        h.put_literal(0, 3); // sharpness
+        h.put_literal(0, 1); // no loop filter deltas
+        h.put_literal(0, 2); // one token partition
+        h.put_literal(qi as u32, 7);
+        for _ in 0..5 {
+            h.put_literal(0, 1); // no per-plane quantizer deltas
+        }
+        h.put_literal(0, 1); // refresh entropy probabilities
+        for probs in COEFF_UPDATE_PROBS.iter().flatten().flatten().flatten() {
+            h.put(false, *probs);
+        }
+        h.put_literal(0, 1); // every macroblock codes its coefficients
+    }
+
+    fn encode_macroblock(&mut self, mbx: usize, mby: usize, left: &mut [bool; 9]) {
+        let top = &mut self.top[mbx];
+
+        // Luma: 16 4x4 blocks whose DCs travel through the Y2 block.
+        let (x0, y0) = (mbx * 16, mby * 16);
+        let edges = Edges::gather(&self.recon[0], x0, y0, 16);
+        let source = self.source[0].block(x0, y0, 16);
+        let (mode, mut pixels) = MODES
+            .iter()
+            .map(|&mode| (mode, edges.predict(mode, 16)))
+            .min_by_key(|(_, pred)| squared_error(&source, pred))
+            .unwrap();
+        for &(bit, prob) in mode.luma_code() {
+            self.header.put(bit, prob);
+        }
+
+        let mut coeffs = [[0; 16]; 16];
+        for (b, block) in coeffs.iter_mut().enumerate() {
+            *block = fdct(&residual(&source, &pixels, 16, b % 4 * 4, b / 4 * 4));
+        }
+        let (y2_levels, mut dcs) = self.y2.quantize(&fwht(&coeffs.map(|c| c[0])), 0);
+        iwht(&mut dcs);
+        let ctx = usize::from(top[0]) + usize::from(left[0]);
+        let nz = write_tokens(&mut self.tokens, &y2_levels, 0, TYPE_Y2, ctx);
+        (top[0], left[0]) = (nz, nz);
+        for (b, block) in coeffs.iter().enumerate() {
+            let (bx, by) = (b % 4, b / 4);
+            let (levels, mut dequantized) = self.y.quantize(block, 1);
+            dequantized[0] = dcs[b];
+            idct(&mut dequantized);
+            add_residual(&mut pixels, &dequantized, 16, bx * 4, by * 4);
+            let ctx = usize::from(top[1 + bx]) + usize::from(left[1 + by]);
+            let nz = write_tokens(&mut self.tokens, &levels, 1, TYPE_Y_AFTER_Y2, ctx);
+            (top[1 + bx], left[1 + by]) = (nz, nz);
+        }
+        self.recon[0].put_block(x0, y0, 16, &pixels);
+
+        // Chroma: U and V share one mode, four 4x4 blocks each.
+        let (x0, y0) = (mbx * 8, mby * 8);
+        let edges = [1, 2].map(|p| Edges::gather(&self.recon[p], x0, y0, 8));
+        let sources = [1, 2].map(|p| self.source[p].block(x0, y0, 8));
+        let (mode, mut predictions) = MODES
+            .iter()
+            .map(|&mode| (mode, [0, 1].map(|i| edges[i].predict(mode, 8))))
+            .min_by_key(|(_, preds)| {
+                (0..2)
+                    .map(|i| squared_error(&sources[i][..64], &preds[i][..64]))
+                    .sum::<u64>()
+            })
+            .unwrap();
+        for &(bit, prob) in mode.chroma_code() {
+            self.header.put(bit, prob);
+        }
+
+        for (i, pixels) in predictions.iter_mut().enumerate() {
+            let ctx_base = 5 + 2 * i;
+            for b in 0..4 {
+                let (bx, by) = (b % 2, b / 2);
+                let block = fdct(&residual(&sources[i], pixels, 8, bx * 4, by * 4));
+                let (levels, mut dequantized) = self.uv.quantize(&block, 0);
+                idct(&mut dequantized);
+                add_residual(pixels, &dequantized, 8, bx * 4, by * 4);
+                let ctx = usize::from(top[ctx_base + bx]) + usize::from(left[ctx_base + by]);
+                let nz = write_tokens(&mut self.tokens, &levels, 0, TYPE_CHROMA, ctx);
+                (top[ctx_base + bx], left[ctx_base + by]) = (nz, nz);
+            }
+            self.recon[1 + i].put_block(x0, y0, 8, pixels);
+        }
+    }
+}
+
+/// Source minus prediction for the 4x4 block at (`x0`, `y0`) of a row-major
+/// `stride`-wide block.
+fn residual(
+    source: &[u8; 256],
+    pred: &[u8; 256],
+    stride: usize,
+    x0: usize,
+    y0: usize,
+) -> [i32; 16] {
+    let mut out = [0; 16];
+    for (i, r) in out.iter_mut().enumerate() {
+        let at = (y0 + i / 4) * stride + x0 + i % 4;
+        *r = i32::from(source[at]) - i32::from(pred[at]);
+    }
+    out
+}
+
+fn add_residual(pixels: &mut [u8; 256], residual: &[i32; 16], stride: usize, x0: usize, y0: usize) {
+    for (i, &r) in residual.iter().enumerate() {
+        let at = (y0 + i / 4) * stride + x0 + i % 4;
+        pixels[at] = (i32::from(pixels[at]) + r).clamp(0, 255) as u8;
+    }
+}
+
+/// Write one block's levels (zigzag order, from position `first`) to the token
+/// partition and return whether any were nonzero, which is the context the
+/// neighbouring blocks see.
+fn write_tokens(
+    enc: &mut BoolEncoder,
+    levels: &[i32; 16],
+    first: usize,
+    block_type: usize,
+    ctx: usize,
+) -> bool {
+    let probs = &DEFAULT_COEFF_PROBS[block_type];
+    let Some(last) = (first..16).rev().find(|&i| levels[i] != 0) else {
+        enc.put(false, probs[BANDS[first]][ctx][0]);
+        return false;
+    };
+
+    let mut ctx = ctx;
+    let mut after_zero = false;
+    for (i, &level) in levels.iter().enumerate().take(last + 1).skip(first) {
+        let p = &probs[BANDS[i]][ctx];
+        // End of block can't directly follow a zero, so that branch is implied.
+        if !after_zero {
+            enc.put(true, p[0]);
+        }
+        let value = level.unsigned_abs();
+        enc.put(value != 0, p[1]);
+        if value == 0 {
+            after_zero = true;
+            ctx = 0;
+            continue;
+        }
+        write_value(enc, value, p);
+        enc.put(level < 0, 128);
+        after_zero = false;
+        ctx = if value == 1 { 1 } else { 2 };
+    }
+    if last < 15 {
+        enc.put(false, probs[BANDS[last + 1]][ctx][0]);
+    }
+    true
+}
+
+/// Write a nonzero magnitude: the rest of the token tree, then any extra bits.
+fn write_value(enc: &mut BoolEncoder, value: u32, p: &[u8; 11]) {
+    enc.put(value > 1, p[2]);
+    if value == 1 {
+        return;
+    }
+    enc.put(value > 4, p[3]);
+    if value <= 4 {
+        enc.put(value > 2, p[4]);
+        if value > 2 {
+            enc.put(value == 4, p[5]);
+        }
+        return;
+    }
+
+    let cat = CATEGORY_BASES
+        .iter()
+        .rposition(|&base| value >= base)
+        .unwrap();
+    enc.put(cat >= 2, p[6]);
+    if cat < 2 {
+        enc.put(cat == 1, p[7]);
+    } else {
+        enc.put(cat >= 4, p[8]);
+        enc.put(cat % 2 == 1, p[if cat < 4 { 9 } else { 10 }]);
+    }
+    let extra = value - CATEGORY_BASES[cat];
+    let bits = CATEGORY_PROBS[cat];
+    for (k, &prob) in bits.iter().enumerate() {
+        enc.put((extra >> (bits.len() - 1 - k)) & 1 != 0, prob);
+    }
+}
+
+/// Forward 4x4 DCT on raster-order input (libvpx `vp8_short_fdct4x4_c`).
+fn fdct(input: &[i32; 16]) -> [i32; 16] {
+    let mut tmp = [0; 16];
+    for (row, out) in input.chunks_exact(4).zip(tmp.chunks_exact_mut(4)) {
+        let a1 = (row[0] + row[3]) * 8;
+        let b1 = (row[1] + row[2]) * 8;
+        let c1 = (row[1] - row[2]) * 8;
+        let d1 = (row[0] - row[3]) * 8;
+        out[0] = a1 + b1;
+        out[2] = a1 - b1;
+        out[1] = (c1 * 2217 + d1 * 5352 + 14500) >> 12;
+        out[3] = (d1 * 2217 - c1 * 5352 + 7500) >> 12;
+    }
+    let mut out = [0; 16];
+    for i in 0..4 {
+        let a1 = tmp[i] + tmp[12 + i];
+        let b1 = tmp[4 + i] + tmp[8 + i];
+        let c1 = tmp[4 + i] - tmp[8 + i];
+        let d1 = tmp[i] - tmp[12 + i];
+        out[i] = (a1 + b1 + 7) >> 4;
+        out[8 + i] = (a1 - b1 + 7) >> 4;
+        out[4 + i] = ((c1 * 2217 + d1 * 5352 + 12000) >> 16) + i32::from(d1 != 0);
+        out[12 + i] = (d1 * 2217 - c1 * 5352 + 51000) >> 16;
+    }
+    out
+}
+
+/// Forward Walsh-Hadamard transform of the 16 luma DCs (libvpx
+/// `vp8_short_walsh4x4_c`).
+fn fwht(input: &[i32; 16]) -> [i32; 16] {
+    let mut tmp = [0; 16];
+    for (row, out) in input.chunks_exact(4).zip(tmp.chunks_exact_mut(4)) {
+        let a1 = (row[0] + row[2]) * 4;
+        let d1 = (row[1] + row[3]) * 4;
+        let c1 = (row[1] - row[3]) * 4;
+        let b1 = (row[0] - row[2]) * 4;
+        out[0] = a1 + d1 + i32::from(a1 != 0);
+        out[1] = b1 + c1;
+        out[2] = b1 - c1;
+        out[3] = a1 - d1;
+    }
+    let mut out = [0; 16];
+    for i in 0..4 {
+        let a1 = tmp[i] + tmp[8 + i];
+        let d1 = tmp[4 + i] + tmp[12 + i];
+        let c1 = tmp[4 + i] - tmp[12 + i];
+        let b1 = tmp[i] - tmp[8 + i];
+        let outputs = [a1 + d1, b1 + c1, b1 - c1, a1 - d1];
+        for (k, v) in outputs.into_iter().enumerate() {
+            out[4 * k + i] = (v + i32::from(v < 0) + 3) >> 3;
+        }
+    }
+    out
+}
+
+/// Inverse 4x4 DCT, bit-exact with the decoder (RFC 6386 14.3).
+fn idct(block: &mut [i32; 16]) {
+    const C1: i64 = 20091;
+    const C2: i64 = 35468;
+    let mut tmp = [0i64; 16];
+    for i in 0..4 {
+        let [x0, x1, x2, x3] = [0, 4, 8, 12].map(|k| i64::from(block[k + i]));
+        let a1 = x0 + x2;
+        let b1 = x0 - x2;
+        let c1 = ((x1 * C2) >> 16) - (x3 + ((x3 * C1) >> 16));
+        let d1 = (x1 + ((x1 * C1) >> 16)) + ((x3 * C2) >> 16);
+        tmp[i] = a1 + d1;
+        tmp[4 + i] = b1 + c1;
+        tmp[8 + i] = b1 - c1;
+        tmp[12 + i] = a1 - d1;
+    }
+    for (row, out) in tmp.chunks_exact(4).zip(block.chunks_exact_mut(4)) {
+        let a1 = row[0] + row[2];
+        let b1 = row[0] - row[2];
+        let c1 = ((row[1] * C2) >> 16) - (row[3] + ((row[3] * C1) >> 16));
+        let d1 = (row[1] + ((row[1] * C1) >> 16)) + ((row[3] * C2) >> 16);
+        out[0] = ((a1 + d1 + 4) >> 3) as i32;
+        out[1] = ((b1 + c1 + 4) >> 3) as i32;
+        out[2] = ((b1 - c1 + 4) >> 3) as i32;
+        out[3] = ((a1 - d1 + 4) >> 3) as i32;
+    }
+}
+
+/// Inverse Walsh-Hadamard transform, bit-exact with the decoder (RFC 6386 14.3).
+fn iwht(block: &mut [i32; 16]) {
+    for i in 0..4 {
+        let a1 = block[i] + block[12 + i];
+        let b1 = block[4 + i] + block[8 + i];
+        let c1 = block[4 + i] - block[8 + i];
+        let d1 = block[i] - block[12 + i];
+        block[i] = a1 + b1;
+        block[4 + i] = c1 + d1;
+        block[8 + i] = a1 - b1;
+        block[12 + i] = d1 - c1;
+    }
+    for row in block.chunks_exact_mut(4) {
+        let a1 = row[0] + row[3];
+        let b1 = row[1] + row[2];
+        let c1 = row[1] - row[2];
+        let d1 = row[0] - row[3];
+        row[0] = (a1 + b1 + 3) >> 3;
+        row[1] = (c1 + d1 + 3) >> 3;
+        row[2] = (a1 - b1 + 3) >> 3;
+        row[3] = (d1 - c1 + 3) >> 3;
+    }
+}
+
+/// Boolean entropy encoder (RFC 6386 7.3).
+struct BoolEncoder {
+    out: Vec<u8>,
+    range: u32,
+    bottom: u32,
+    bit_count: u32,
+}
+
+impl BoolEncoder {
+    fn new() -> Self {
+        BoolEncoder {
+            out: Vec::new(),
+            range: 255,
+            bottom: 0,
+            bit_count: 24,
+        }
+    }
+
+    /// Code `bit`, where `prob` / 256 is the chance of it being false.
+    fn put(&mut self, bit: bool, prob: u8) {
+        let split = 1 + (((self.range - 1) * u32::from(prob)) >> 8);
+        if bit {
+            self.bottom += split;
+            self.range -= split;
+        } else {
+            self.range = split;
+        }
+        while self.range < 128 {
+            self.range <<= 1;
+            if self.bottom & (1 << 31) != 0 {
+                self.carry();
+            }
+            self.bottom <<= 1;
+            self.bit_count -= 1;
+            if self.bit_count == 0 {
+                self.out.push((self.bottom >> 24) as u8);
+                self.bottom &= (1 << 24) - 1;
+                self.bit_count = 8;
+            }
+        }
+    }
+
+    /// Code the low `bits` bits of `value`, most significant first, at even odds.
+    fn put_literal(&mut self, value: u32, bits: u32) {
+        for bit in (0..bits).rev() {
+            self.put((value >> bit) & 1 != 0, 128);
+        }
+    }
+
+    /// Propagate a carry into the bytes already written.
+    fn carry(&mut self) {
+        for byte in self.out.iter_mut().rev() {
+            if *byte == 255 {
+                *byte = 0;
+            } else {
+                *byte += 1;
+                break;
+            }
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let c = self.bit_count;
+        let mut v = self.bottom;
+        if v & (1 << (32 - c)) != 0 {
+            self.carry();
+        }
+        v <<= c & 7;
+        for _ in 0..c >> 3 {
+            v <<= 8;
+        }
+        for _ in 0..4 {
+            self.out.push((v >> 24) as u8);
+            v <<= 8;
+        }
+        self.out
+    }
+}
+
+const BANDS: [usize; 16] = [0, 1, 2, 3, 6, 4, 5, 6, 6, 6, 6, 6, 6, 6, 6, 7];
+
+const ZIGZAG: [usize; 16] = [0, 1, 4, 8, 5, 2, 3, 6, 9, 12, 13, 10, 7, 11, 14, 15];
+
+/// Smallest magnitude of each DCT_CAT token and the probabilities of its extra bits.
+const CATEGORY_BASES: [u32; 6] = [5, 7, 11, 19, 35, 67];
+const CATEGORY_PROBS: [&[u8]; 6] = [
+    &[159],
+    &[165, 145],
+    &[173, 148, 140],
+    &[176, 155, 140, 135],
+    &[180, 157, 141, 134, 130],
+    &[254, 254, 243, 230, 196, 177, 153, 140, 133, 130, 129],
+];
+
+/// Step sizes by quantizer index (RFC 6386 14.1).
+#[rustfmt::skip]
+const DC_QUANT: [u16; 128] = [
+      4,   5,   6,   7,   8,   9,  10,  10,
+     11,  12,  13,  14,  15,  16,  17,  17,
+     18,  19,  20,  20,  21,  21,  22,  22,
+     23,  23,  24,  25,  25,  26,  27,  28,
+     29,  30,  31,  32,  33,  34,  35,  36,
+     37,  37,  38,  39,  40,  41,  42,  43,
+     44,  45,  46,  46,  47,  48,  49,  50,
+     51,  52,  53,  54,  55,  56,  57,  58,
+     59,  60,  61,  62,  63,  64,  65,  66,
+     67,  68,  69,  70,  71,  72,  73,  74,
+     75,  76,  76,  77,  78,  79,  80,  81,
+     82,  83,  84,  85,  86,  87,  88,  89,
+     91,  93,  95,  96,  98, 100, 101, 102,
+    104, 106, 108, 110, 112, 114, 116, 118,
+    122, 124, 126, 128, 130, 132, 134, 136,
+    138, 140, 143, 145, 148, 151, 154, 157,
+];
+
+#[rustfmt::skip]
+const AC_QUANT: [u16; 128] = [
+      4,   5,   6,   7,   8,   9,  10,  11,
+     12,  13,  14,  15,  16,  17,  18,  19,
+     20,  21,  22,  23,  24,  25,  26,  27,
+     28,  29,  30,  31,  32,  33,  34,  35,
+     36,  37,  38,  39,  40,  41,  42,  43,
+     44,  45,  46,  47,  48,  49,  50,  51,
+     52,  53,  54,  55,  56,  57,  58,  60,
+     62,  64,  66,  68,  70,  72,  74,  76,
+     78,  80,  82,  84,  86,  88,  90,  92,
+     94,  96,  98, 100, 102, 104, 106, 108,
+    110, 112, 114, 116, 119, 122, 125, 128,
+    131, 134, 137, 140, 143, 146, 149, 152,
+    155, 158, 161, 164, 167, 170, 173, 177,
+    181, 185, 189, 193, 197, 201, 205, 209,
+    213, 217, 221, 225, 229, 234, 239, 245,
+    249, 254, 259, 264, 269, 274, 279, 284,
+];
+
+/// Probability that each token probability is updated in the frame header (RFC 6386 13.4).
+#[rustfmt::skip]
+const COEFF_UPDATE_PROBS: TokenProbs = [
+    [
+        [
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [176, 246, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [223, 241, 252, 255, 255, 255, 255, 255, 255, 255, 255],
+            [249, 253, 253, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 244, 252, 255, 255, 255, 255, 255, 255, 255, 255],
+            [234, 254, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [253, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 246, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [239, 253, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [254, 255, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 248, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [251, 255, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 253, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [251, 254, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [254, 255, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 254, 253, 255, 254, 255, 255, 255, 255, 255, 255],
+            [250, 255, 254, 255, 254, 255, 255, 255, 255, 255, 255],
+            [254, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+    ],
+    [
+        [
+            [217, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [225, 252, 241, 253, 255, 255, 254, 255, 255, 255, 255],
+            [234, 250, 241, 250, 253, 255, 253, 254, 255, 255, 255],
+        ],
+        [
+            [255, 254, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [223, 254, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [238, 253, 254, 254, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 248, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [249, 254, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 253, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [247, 254, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 253, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [252, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 254, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [253, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 254, 253, 255, 255, 255, 255, 255, 255, 255, 255],
+            [250, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [254, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+    ],
+    [
+        [
+            [186, 251, 250, 255, 255, 255, 255, 255, 255, 255, 255],
+            [234, 251, 244, 254, 255, 255, 255, 255, 255, 255, 255],
+            [251, 251, 243, 253, 254, 255, 254, 255, 255, 255, 255],
+        ],
+        [
+            [255, 253, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [236, 253, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [251, 253, 253, 254, 254, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 254, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [254, 254, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 254, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [254, 254, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [254, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [254, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+    ],
+    [
+        [
+            [248, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [250, 254, 252, 254, 255, 255, 255, 255, 255, 255, 255],
+            [248, 254, 249, 253, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 253, 253, 255, 255, 255, 255, 255, 255, 255, 255],
+            [246, 253, 253, 255, 255, 255, 255, 255, 255, 255, 255],
+            [252, 254, 251, 254, 254, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 254, 252, 255, 255, 255, 255, 255, 255, 255, 255],
+            [248, 254, 253, 255, 255, 255, 255, 255, 255, 255, 255],
+            [253, 255, 254, 254, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 251, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [245, 251, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [253, 253, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 251, 253, 255, 255, 255, 255, 255, 255, 255, 255],
+            [252, 253, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 254, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 252, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [249, 255, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 255, 253, 255, 255, 255, 255, 255, 255, 255, 255],
+            [250, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [254, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+    ],
+];
+
+/// Default token probabilities, indexed by block type, band, context and tree node
+/// (RFC 6386 13.5).
+#[rustfmt::skip]
+const DEFAULT_COEFF_PROBS: TokenProbs = [
+    [
+        [
+            [128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128],
+            [128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128],
+            [128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128],
+        ],
+        [
+            [253, 136, 254, 255, 228, 219, 128, 128, 128, 128, 128],
+            [189, 129, 242, 255, 227, 213, 255, 219, 128, 128, 128],
+            [106, 126, 227, 252, 214, 209, 255, 255, 128, 128, 128],
+        ],
+        [
+            [1, 98, 248, 255, 236, 226, 255, 255, 128, 128, 128],
+            [181, 133, 238, 254, 221, 234, 255, 154, 128, 128, 128],
+            [78, 134, 202, 247, 198, 180, 255, 219, 128, 128, 128],
+        ],
+        [
+            [1, 185, 249, 255, 243, 255, 128, 128, 128, 128, 128],
+            [184, 150, 247, 255, 236, 224, 128, 128, 128, 128, 128],
+            [77, 110, 216, 255, 236, 230, 128, 128, 128, 128, 128],
+        ],
+        [
+            [1, 101, 251, 255, 241, 255, 128, 128, 128, 128, 128],
+            [170, 139, 241, 252, 236, 209, 255, 255, 128, 128, 128],
+            [37, 116, 196, 243, 228, 255, 255, 255, 128, 128, 128],
+        ],
+        [
+            [1, 204, 254, 255, 245, 255, 128, 128, 128, 128, 128],
+            [207, 160, 250, 255, 238, 128, 128, 128, 128, 128, 128],
+            [102, 103, 231, 255, 211, 171, 128, 128, 128, 128, 128],
+        ],
+        [
+            [1, 152, 252, 255, 240, 255, 128, 128, 128, 128, 128],
+            [177, 135, 243, 255, 234, 225, 128, 128, 128, 128, 128],
+            [80, 129, 211, 255, 194, 224, 128, 128, 128, 128, 128],
+        ],
+        [
+            [1, 1, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+            [246, 1, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+            [255, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128],
+        ],
+    ],
+    [
+        [
+            [198, 35, 237, 223, 193, 187, 162, 160, 145, 155, 62],
+            [131, 45, 198, 221, 172, 176, 220, 157, 252, 221, 1],
+            [68, 47, 146, 208, 149, 167, 221, 162, 255, 223, 128],
+        ],
+        [
+            [1, 149, 241, 255, 221, 224, 255, 255, 128, 128, 128],
+            [184, 141, 234, 253, 222, 220, 255, 199, 128, 128, 128],
+            [81, 99, 181, 242, 176, 190, 249, 202, 255, 255, 128],
+        ],
+        [
+            [1, 129, 232, 253, 214, 197, 242, 196, 255, 255, 128],
+            [99, 121, 210, 250, 201, 198, 255, 202, 128, 128, 128],
+            [23, 91, 163, 242, 170, 187, 247, 210, 255, 255, 128],
+        ],
+        [
+            [1, 200, 246, 255, 234, 255, 128, 128, 128, 128, 128],
+            [109, 178, 241, 255, 231, 245, 255, 255, 128, 128, 128],
+            [44, 130, 201, 253, 205, 192, 255, 255, 128, 128, 128],
+        ],
+        [
+            [1, 132, 239, 251, 219, 209, 255, 165, 128, 128, 128],
+            [94, 136, 225, 251, 218, 190, 255, 255, 128, 128, 128],
+            [22, 100, 174, 245, 186, 161, 255, 199, 128, 128, 128],
+        ],
+        [
+            [1, 182, 249, 255, 232, 235, 128, 128, 128, 128, 128],
+            [124, 143, 241, 255, 227, 234, 128, 128, 128, 128, 128],
+            [35, 77, 181, 251, 193, 211, 255, 205, 128, 128, 128],
+        ],
+        [
+            [1, 157, 247, 255, 236, 231, 255, 255, 128, 128, 128],
+            [121, 141, 235, 255, 225, 227, 255, 255, 128, 128, 128],
+            [45, 99, 188, 251, 195, 217, 255, 224, 128, 128, 128],
+        ],
+        [
+            [1, 1, 251, 255, 213, 255, 128, 128, 128, 128, 128],
+            [203, 1, 248, 255, 255, 128, 128, 128, 128, 128, 128],
+            [137, 1, 177, 255, 224, 255, 128, 128, 128, 128, 128],
+        ],
+    ],
+    [
+        [
+            [253, 9, 248, 251, 207, 208, 255, 192, 128, 128, 128],
+            [175, 13, 224, 243, 193, 185, 249, 198, 255, 255, 128],
+            [73, 17, 171, 221, 161, 179, 236, 167, 255, 234, 128],
+        ],
+        [
+            [1, 95, 247, 253, 212, 183, 255, 255, 128, 128, 128],
+            [239, 90, 244, 250, 211, 209, 255, 255, 128, 128, 128],
+            [155, 77, 195, 248, 188, 195, 255, 255, 128, 128, 128],
+        ],
+        [
+            [1, 24, 239, 251, 218, 219, 255, 205, 128, 128, 128],
+            [201, 51, 219, 255, 196, 186, 128, 128, 128, 128, 128],
+            [69, 46, 190, 239, 201, 218, 255, 228, 128, 128, 128],
+        ],
+        [
+            [1, 191, 251, 255, 255, 128, 128, 128, 128, 128, 128],
+            [223, 165, 249, 255, 213, 255, 128, 128, 128, 128, 128],
+            [141, 124, 248, 255, 255, 128, 128, 128, 128, 128, 128],
+        ],
+        [
+            [1, 16, 248, 255, 255, 128, 128, 128, 128, 128, 128],
+            [190, 36, 230, 255, 236, 255, 128, 128, 128, 128, 128],
+            [149, 1, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+        ],
+        [
+            [1, 226, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+            [247, 192, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+            [240, 128, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+        ],
+        [
+            [1, 134, 252, 255, 255, 128, 128, 128, 128, 128, 128],
+            [213, 62, 250, 255, 255, 128, 128, 128, 128, 128, 128],
+            [55, 93, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+        ],
+        [
+            [128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128],
+            [128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128],
+            [128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128],
+        ],
+    ],
+    [
+        [
+            [202, 24, 213, 235, 186, 191, 220, 160, 240, 175, 255],
+            [126, 38, 182, 232, 169, 184, 228, 174, 255, 187, 128],
+            [61, 46, 138, 219, 151, 178, 240, 170, 255, 216, 128],
+        ],
+        [
+            [1, 112, 230, 250, 199, 191, 247, 159, 255, 255, 128],
+            [166, 109, 228, 252, 211, 215, 255, 174, 128, 128, 128],
+            [39, 77, 162, 232, 172, 180, 245, 178, 255, 255, 128],
+        ],
+        [
+            [1, 52, 220, 246, 198, 199, 249, 220, 255, 255, 128],
+            [124, 74, 191, 243, 183, 193, 250, 221, 255, 255, 128],
+            [24, 71, 130, 219, 154, 170, 243, 182, 255, 255, 128],
+        ],
+        [
+            [1, 182, 225, 249, 219, 240, 255, 224, 128, 128, 128],
+            [149, 150, 226, 252, 216, 205, 255, 171, 128, 128, 128],
+            [28, 108, 170, 242, 183, 194, 254, 223, 255, 255, 128],
+        ],
+        [
+            [1, 81, 230, 252, 204, 203, 255, 192, 128, 128, 128],
+            [123, 102, 209, 247, 188, 196, 255, 233, 128, 128, 128],
+            [20, 95, 153, 243, 164, 173, 255, 203, 128, 128, 128],
+        ],
+        [
+            [1, 222, 248, 255, 216, 213, 128, 128, 128, 128, 128],
+            [168, 175, 246, 252, 235, 205, 255, 255, 128, 128, 128],
+            [47, 116, 215, 255, 211, 212, 255, 255, 128, 128, 128],
+        ],
+        [
+            [1, 121, 236, 253, 212, 214, 255, 255, 128, 128, 128],
+            [141, 84, 213, 252, 201, 202, 255, 219, 128, 128, 128],
+            [42, 80, 160, 240, 162, 185, 255, 205, 128, 128, 128],
+        ],
+        [
+            [1, 1, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+            [244, 1, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+            [238, 1, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+        ],
+    ],
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Gradients with some per-pixel noise, so every mode and token size shows up.
+    fn sample_image(width: usize, height: usize) -> Vec<u8> {
+        let mut seed = 12345u32;
+        let mut rgba = Vec::with_capacity(width * height * 4);
+        for y in 0..height {
+            for x in 0..width {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                let noise = (seed >> 24) as usize % 40;
+                rgba.extend([
+                    (x * 255 / width + noise).min(255) as u8,
+                    (y * 255 / height + noise / 2).min(255) as u8,
+                    ((x ^ y) * 7 % 256) as u8,
+                    255,
+                ]);
+            }
+        }
+        rgba
+    }
+
+    #[test]
+    fn reconstruction_matches_decoder() {
+        // Quality 100 also turns the loop filter off, so the decoder's planes
+        // should be exactly what the encoder predicted from.
+        for (width, height) in [(1, 1), (37, 23), (100, 3)] {
+            let rgba = sample_image(width, height);
+            let mut encoder = Encoder::new(&rgba, width, height, 0);
+            for mby in 0..encoder.mb_height {
+                let mut left = [false; 9];
+                for mbx in 0..encoder.mb_width {
+                    encoder.encode_macroblock(mbx, mby, &mut left);
+                }
+            }
+
+            let bytes = encode_vp8(&rgba, width as u32, height as u32, 100.0).unwrap();
+            let frame = image_webp::vp8::Vp8Decoder::decode_frame(Cursor::new(bytes)).unwrap();
+            assert_eq!(
+                (frame.width as usize, frame.height as usize),
+                (width, height)
+            );
+            assert_eq!(frame.ybuf, encoder.recon[0].data);
+            assert_eq!(frame.ubuf, encoder.recon[1].data);
+            assert_eq!(frame.vbuf, encoder.recon[2].data);
+        }
+    }
+
+    #[test]
+    fn transforms_round_trip_within_one() {
+        let mut seed = 1u32;
+        let mut next = |span: u32| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            ((seed >> 16) % span) as i32
+        };
+        for _ in 0..1000 {
+            let pixels: [i32; 16] = std::array::from_fn(|_| next(511) - 255);
+            let mut block = fdct(&pixels);
+            idct(&mut block);
+            assert!(block.iter().zip(&pixels).all(|(a, b)| (a - b).abs() <= 1));
+
+            let dcs: [i32; 16] = std::array::from_fn(|_| next(4081) - 2040);
+            let mut block = fwht(&dcs);
+            iwht(&mut block);
+            assert!(block.iter().zip(&dcs).all(|(a, b)| (a - b).abs() <= 1));
+        }
+    }
+}
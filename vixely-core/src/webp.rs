@@ -0,0 +1,253 @@
+//! Animated WebP encoding.
+
+use image_webp::{ColorType, WebPEncoder};
+use wasm_bindgen::prelude::*;
+
+use crate::frames::{checked_frames, encode_error};
+use crate::vp8;
+
+/// Largest canvas side the VP8L bitstream can describe.
+const MAX_DIMENSION: u32 = 16384;
+
+/// Encode RGBA frames into an animated WebP.
+///
+/// `rgba_data`: all frames concatenated (width * height * 4 bytes per frame)
+/// `width`, `height`: frame dimensions, 1-16384 (1-16383 when lossy)
+/// `frame_count`: number of frames, at least 1
+/// `delay_ms`: how long each frame shows, in milliseconds
+/// `quality`: 0-100, only used when `lossless` is false
+/// `lossless`: store frames as exact VP8L instead of lossy VP8
+///
+/// Lossy frames keep alpha exact in a losslessly compressed ALPH chunk, which
+/// is left out for fully opaque frames. The animation loops forever.
+///
+/// Returns an error if a dimension is out of range, `frame_count` is 0,
+/// `rgba_data` holds fewer than `frame_count` frames, or encoding fails.
+#[wasm_bindgen]
+pub fn encode_webp_frames(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    frame_count: u32,
+    delay_ms: u32,
+    quality: f32,
+    lossless: bool,
+) -> Result<Vec<u8>, JsValue> {
+    let max = if lossless {
+        MAX_DIMENSION
+    } else {
+        vp8::MAX_DIMENSION
+    };
+    if width == 0 || height == 0 || width > max || height > max {
+        return Err(JsValue::from_str(&format!(
+            "WebP frames must be 1-{max} pixels per side, got {width}x{height}"
+        )));
+    }
+    if frame_count == 0 {
+        return Err(JsValue::from_str("animated WebP needs at least one frame"));
+    }
+    let (frames, frame_size) = checked_frames(rgba_data, width, height, frame_count)?;
+
+    let mut body = Vec::new();
+    write_chunk(&mut body, b"VP8X", &vp8x_payload(width, height));
+    // Background color (BGRA, unused by browsers) and loop count 0 = forever.
+    write_chunk(&mut body, b"ANIM", &[0, 0, 0, 0, 0, 0]);
+
+    for (i, frame) in frames.chunks_exact(frame_size).enumerate() {
+        let mut anmf = Vec::new();
+        anmf.extend_from_slice(&[0; 6]); // frame offset x / 2, y / 2
+        anmf.extend_from_slice(&u24(width - 1));
+        anmf.extend_from_slice(&u24(height - 1));
+        anmf.extend_from_slice(&u24(delay_ms.min(0xFF_FFFF)));
+        // Replace the canvas instead of alpha-blending, and don't dispose.
+        anmf.push(0b10);
+        encode_frame(&mut anmf, frame, width, height, quality, lossless)
+            .map_err(|e| encode_error(&format!("failed to encode frame {i}"), e))?;
+        write_chunk(&mut body, b"ANMF", &anmf);
+    }
+
+    let mut output = Vec::with_capacity(12 + body.len());
+    output.extend_from_slice(b"RIFF");
+    output.extend_from_slice(&(body.len() as u32 + 4).to_le_bytes());
+    output.extend_from_slice(b"WEBP");
+    output.extend_from_slice(&body);
+    Ok(output)
+}
+
+/// VP8X header flagging alpha and animation on a `width` x `height` canvas.
+fn vp8x_payload(width: u32, height: u32) -> Vec<u8> {
+    let mut payload = vec![0b0001_0010, 0, 0, 0];
+    payload.extend_from_slice(&u24(width - 1));
+    payload.extend_from_slice(&u24(height - 1));
+    payload
+}
+
+/// Append the image chunks of one frame: VP8L, or ALPH (when needed) and VP8.
+fn encode_frame(
+    out: &mut Vec<u8>,
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    quality: f32,
+    lossless: bool,
+) -> Result<(), String> {
+    if lossless {
+        write_chunk(
+            out,
+            b"VP8L",
+            &encode_vp8l(pixels, width, height, ColorType::Rgba8)?,
+        );
+        return Ok(());
+    }
+
+    if pixels.chunks_exact(4).any(|px| px[3] != 255) {
+        let alpha: Vec<u8> = pixels.chunks_exact(4).map(|px| px[3]).collect();
+        let bitstream = encode_vp8l(&alpha, width, height, ColorType::L8)?;
+        // Lossless compression, no filtering or preprocessing; the VP8L
+        // stream goes in without its 5-byte header since the size is implied.
+        let mut payload = vec![0b01];
+        payload.extend_from_slice(&bitstream[5..]);
+        write_chunk(out, b"ALPH", &payload);
+    }
+    write_chunk(
+        out,
+        b"VP8 ",
+        &vp8::encode_vp8(pixels, width, height, quality)?,
+    );
+    Ok(())
+}
+
+/// Encode one image and return just its VP8L bitstream.
+fn encode_vp8l(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    color: ColorType,
+) -> Result<Vec<u8>, String> {
+    let mut file = Vec::new();
+    WebPEncoder::new(&mut file)
+        .encode(pixels, width, height, color)
+        .map_err(|e| e.to_string())?;
+
+    // A simple-format file is "RIFF" size "WEBP" followed by one VP8L chunk.
+    match file.get(12..20) {
+        Some(header) if &header[..4] == b"VP8L" => {
+            let len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+            file.get(20..20 + len)
+                .map(<[u8]>::to_vec)
+                .ok_or_else(|| "truncated VP8L chunk".to_string())
+        }
+        _ => Err("encoder did not produce a VP8L chunk".to_string()),
+    }
+}
+
+fn u24(v: u32) -> [u8; 3] {
+    let [a, b, c, _] = v.to_le_bytes();
+    [a, b, c]
+}
+
+/// Append a RIFF chunk, padding odd payloads to an even length.
+fn write_chunk(out: &mut Vec<u8>, fourcc: &[u8; 4], payload: &[u8]) {
+    out.extend_from_slice(fourcc);
+    out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    out.extend_from_slice(payload);
+    if !payload.len().is_multiple_of(2) {
+        out.push(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image_webp::WebPDecoder;
+    use std::io::Cursor;
+
+    const W: u32 = 48;
+    const H: u32 = 40;
+
+    /// Two frames of noisy gradients with an alpha ramp down the left edge.
+    fn sample_frames() -> Vec<u8> {
+        let mut seed = 7u32;
+        let mut frames = Vec::new();
+        for f in 0..2 {
+            for y in 0..H {
+                for x in 0..W {
+                    seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                    let noise = (seed >> 24) % 24;
+                    frames.extend([
+                        (x * 5 + f * 40 + noise).min(255) as u8,
+                        (y * 6 + noise).min(255) as u8,
+                        ((x + y) * 2) as u8,
+                        (x * 32).min(255) as u8,
+                    ]);
+                }
+            }
+        }
+        frames
+    }
+
+    fn encode(frames: &[u8], quality: f32, lossless: bool) -> Vec<u8> {
+        encode_webp_frames(frames, W, H, 2, 50, quality, lossless).unwrap()
+    }
+
+    fn decode(webp: &[u8]) -> Vec<u8> {
+        let mut decoder = WebPDecoder::new(Cursor::new(webp)).unwrap();
+        assert_eq!(decoder.dimensions(), (W, H));
+        assert_eq!(decoder.num_frames(), 2);
+        let mut frames = Vec::new();
+        for _ in 0..2 {
+            let mut frame = vec![0; decoder.output_buffer_size().unwrap()];
+            assert_eq!(decoder.read_frame(&mut frame).unwrap(), 50);
+            frames.extend(frame);
+        }
+        frames
+    }
+
+    #[test]
+    fn lossless_round_trips_exactly() {
+        let frames = sample_frames();
+        assert_eq!(decode(&encode(&frames, 0.0, true)), frames);
+    }
+
+    #[test]
+    fn lossy_keeps_alpha_exact_and_color_close() {
+        let frames = sample_frames();
+        let decoded = decode(&encode(&frames, 90.0, false));
+        assert_eq!(decoded.len(), frames.len());
+
+        let mut squared = 0.0;
+        let mut samples = 0.0;
+        for (src, out) in frames.chunks_exact(4).zip(decoded.chunks_exact(4)) {
+            assert_eq!(out[3], src[3]);
+            for c in 0..3 {
+                squared += (f64::from(src[c]) - f64::from(out[c])).powi(2);
+                samples += 1.0;
+            }
+        }
+        let psnr = 10.0 * (255.0 * 255.0 * samples / squared).log10();
+        assert!(psnr > 30.0, "PSNR {psnr:.1} dB");
+    }
+
+    #[test]
+    fn lossy_is_smaller_than_lossless() {
+        let frames = sample_frames();
+        let lossy = encode(&frames, 75.0, false);
+        let lossless = encode(&frames, 75.0, true);
+        assert!(
+            lossy.len() < lossless.len(),
+            "{} vs {}",
+            lossy.len(),
+            lossless.len()
+        );
+        assert!(encode(&frames, 20.0, false).len() < lossy.len());
+    }
+
+    #[test]
+    fn opaque_lossy_frames_skip_the_alpha_chunk() {
+        let mut frames = sample_frames();
+        frames.chunks_exact_mut(4).for_each(|px| px[3] = 255);
+        let webp = encode(&frames, 75.0, false);
+        assert!(!webp.windows(4).any(|w| w == b"ALPH"));
+        assert!(decode(&webp).chunks_exact(4).all(|px| px[3] == 255));
+    }
+}